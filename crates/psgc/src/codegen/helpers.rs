@@ -0,0 +1,109 @@
+//! # Helper Functions
+//!
+//! Non-trivial nodes are emitted once per module as WGSL helper functions
+//! instead of being inlined at every use.
+
+use super::template;
+use crate::types::{self, GenericBindings};
+use graphy::core::NodeMetadata;
+
+/// Name of the helper function generated for a node type
+///
+/// Generic node types get one helper per instantiation, named after the
+/// concrete types, e.g. `node_lerp_vec3f`.
+pub fn helper_name(node_type: &str, bindings: &GenericBindings) -> String {
+    let mut name = format!("node_{}", node_type);
+    for concrete in bindings.values() {
+        name.push('_');
+        name.push_str(&concrete.short_name());
+    }
+    name
+}
+
+/// Whether a node type is emitted as a helper function rather than inlined
+///
+/// Multi-statement sources can't be inlined into an expression, and sources
+/// that reference a param more than once would duplicate the argument
+/// expression at every use.
+pub fn needs_helper(node_meta: &NodeMetadata) -> bool {
+    let source = node_meta.function_source.trim();
+
+    is_statement_body(source)
+        || node_meta
+            .params
+            .iter()
+            .any(|param| template::count_uses(source, &param.name) > 1)
+}
+
+/// Generate the helper function for a node type
+///
+/// Expression sources become `return <source>;`. Statement sources are used
+/// as the function body verbatim and must contain their own `return`.
+/// Generic params and return types use the concrete types in `bindings`.
+pub fn generate_helper_function(node_meta: &NodeMetadata, bindings: &GenericBindings) -> String {
+    let mut code = String::new();
+    let name = helper_name(&node_meta.name, bindings);
+
+    let params: Vec<String> = node_meta
+        .params
+        .iter()
+        .map(|param| format!("{}: {}", param.name, types::resolved_param_type(param, bindings)))
+        .collect();
+
+    match types::resolved_return_type(node_meta, bindings) {
+        Some(return_type) => code.push_str(&format!(
+            "fn {}({}) -> {} {{\n",
+            name,
+            params.join(", "),
+            return_type
+        )),
+        None => code.push_str(&format!(
+            "fn {}({}) {{\n",
+            name,
+            params.join(", ")
+        )),
+    }
+
+    let source = node_meta.function_source.trim();
+    if is_statement_body(source) {
+        for line in dedent(source) {
+            code.push_str(&format!("    {}\n", line));
+        }
+    } else if node_meta.return_type.is_some() {
+        code.push_str(&format!("    return {};\n", source));
+    } else {
+        code.push_str(&format!("    {};\n", source));
+    }
+
+    code.push_str("}\n");
+
+    code
+}
+
+/// Whether a node source is a statement body rather than a single expression
+fn is_statement_body(source: &str) -> bool {
+    source.contains(';')
+}
+
+/// Split a multi-line source into lines with common indentation removed
+///
+/// The first line is skipped when measuring indentation since it usually
+/// starts right after the opening quote of the source string.
+fn dedent(source: &str) -> Vec<&str> {
+    let lines: Vec<&str> = source.lines().map(str::trim_end).collect();
+
+    let indent = lines
+        .iter()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .into_iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| if i == 0 { line.trim_start() } else { &line[indent..] })
+        .collect()
+}
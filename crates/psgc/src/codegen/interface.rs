@@ -0,0 +1,177 @@
+//! # Shader Stage Interface
+//!
+//! Vertex attributes, inter-stage varyings and compute builtins used by the
+//! generated entry points.
+
+use crate::diagnostics::{Diagnostic, DiagnosticCode};
+use graphy::{NodeInstance, PropertyValue};
+
+/// A vertex attribute read by one of the wgsl_std vertex input nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexInput {
+    /// Node type that reads the attribute (also the WGSL parameter name)
+    pub node_type: &'static str,
+    /// Fixed `@location` of the attribute in the vertex buffer layout
+    pub location: u32,
+    /// WGSL type of the attribute
+    pub wgsl_type: &'static str,
+}
+
+/// Vertex attributes, in location order
+pub const VERTEX_INPUTS: &[VertexInput] = &[
+    VertexInput { node_type: "vertex_position", location: 0, wgsl_type: "vec3<f32>" },
+    VertexInput { node_type: "vertex_normal", location: 1, wgsl_type: "vec3<f32>" },
+    VertexInput { node_type: "vertex_uv", location: 2, wgsl_type: "vec2<f32>" },
+    VertexInput { node_type: "vertex_color", location: 3, wgsl_type: "vec4<f32>" },
+];
+
+/// An optional `vertex_output` param written to the `VertexOutput` struct
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Varying {
+    /// Param name on `vertex_output` (also the struct field name)
+    pub name: &'static str,
+    /// Fixed `@location` of the varying, the same in every stage
+    ///
+    /// Stages compiled separately only link if both sides agree on the
+    /// location of each varying, whichever varyings each of them uses.
+    pub location: u32,
+    /// WGSL type of the varying
+    pub wgsl_type: &'static str,
+}
+
+/// Custom vertex outputs, in location order
+pub const VARYINGS: &[Varying] = &[
+    Varying { name: "uv", location: 0, wgsl_type: "vec2<f32>" },
+    Varying { name: "normal", location: 1, wgsl_type: "vec3<f32>" },
+    Varying { name: "color", location: 2, wgsl_type: "vec4<f32>" },
+    Varying { name: "world_position", location: 3, wgsl_type: "vec3<f32>" },
+];
+
+/// Generate the `VertexOutput` struct for the given varyings
+pub fn generate_vertex_output_struct(varyings: &[&Varying]) -> String {
+    let mut code = String::new();
+
+    code.push_str("struct VertexOutput {\n");
+    code.push_str("    @builtin(position) position: vec4<f32>,\n");
+    for varying in varyings {
+        code.push_str(&format!(
+            "    @location({}) {}: {},\n",
+            varying.location, varying.name, varying.wgsl_type
+        ));
+    }
+    code.push_str("}\n");
+
+    code
+}
+
+/// A value read by one of the wgsl_std fragment input nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentInput {
+    /// Node type that reads the input (also the WGSL identifier it binds)
+    pub node_type: &'static str,
+    /// `VertexOutput` field the input is read from
+    pub field: &'static str,
+    /// WGSL type of the input
+    pub wgsl_type: &'static str,
+}
+
+impl FragmentInput {
+    /// Whether this input is the `@builtin(position)` rather than a varying
+    pub fn is_builtin_position(&self) -> bool {
+        self.field == "position"
+    }
+
+    /// The varying this input reads, `None` for the builtin position
+    pub fn varying(&self) -> Option<&'static Varying> {
+        VARYINGS.iter().find(|varying| varying.name == self.field)
+    }
+}
+
+/// Fragment inputs, in the same order as [`VARYINGS`]
+pub const FRAGMENT_INPUTS: &[FragmentInput] = &[
+    FragmentInput { node_type: "frag_position", field: "position", wgsl_type: "vec4<f32>" },
+    FragmentInput { node_type: "frag_uv", field: "uv", wgsl_type: "vec2<f32>" },
+    FragmentInput { node_type: "frag_normal", field: "normal", wgsl_type: "vec3<f32>" },
+    FragmentInput { node_type: "frag_color", field: "color", wgsl_type: "vec4<f32>" },
+    FragmentInput {
+        node_type: "frag_world_position",
+        field: "world_position",
+        wgsl_type: "vec3<f32>",
+    },
+];
+
+/// A compute builtin read by one of the wgsl_std compute input nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBuiltin {
+    /// Node type that reads the builtin (also the builtin and parameter name)
+    pub node_type: &'static str,
+    /// WGSL type of the builtin
+    pub wgsl_type: &'static str,
+}
+
+/// Compute builtins, in parameter order
+pub const COMPUTE_BUILTINS: &[ComputeBuiltin] = &[
+    ComputeBuiltin { node_type: "global_invocation_id", wgsl_type: "vec3<u32>" },
+    ComputeBuiltin { node_type: "local_invocation_id", wgsl_type: "vec3<u32>" },
+    ComputeBuiltin { node_type: "local_invocation_index", wgsl_type: "u32" },
+    ComputeBuiltin { node_type: "workgroup_id", wgsl_type: "vec3<u32>" },
+    ComputeBuiltin { node_type: "num_workgroups", wgsl_type: "vec3<u32>" },
+];
+
+/// Workgroup size used when `compute_main` doesn't set one
+pub const DEFAULT_WORKGROUP_SIZE: [u32; 3] = [8, 8, 1];
+
+/// `compute_main` properties holding the workgroup size, in x/y/z order
+pub const WORKGROUP_SIZE_PROPERTIES: [&str; 3] =
+    ["workgroup_size_x", "workgroup_size_y", "workgroup_size_z"];
+
+/// WebGPU default limits on a single workgroup dimension, in x/y/z order
+pub const MAX_WORKGROUP_SIZE: [u32; 3] = [256, 256, 64];
+
+/// WebGPU default limit on invocations per workgroup
+pub const MAX_WORKGROUP_INVOCATIONS: u32 = 256;
+
+/// Read the workgroup size from a `compute_main` node's properties
+///
+/// Missing axes fall back to [`DEFAULT_WORKGROUP_SIZE`]. Each axis must be a
+/// positive integer within the WebGPU default limits.
+pub fn workgroup_size(entry_node: &NodeInstance) -> Result<[u32; 3], Diagnostic> {
+    let mut size = DEFAULT_WORKGROUP_SIZE;
+
+    for (axis, property) in WORKGROUP_SIZE_PROPERTIES.iter().enumerate() {
+        match entry_node.properties.get(*property) {
+            None => {}
+            Some(PropertyValue::Number(value))
+                if value.fract() == 0.0
+                    && *value >= 1.0
+                    && *value <= MAX_WORKGROUP_SIZE[axis] as f64 =>
+            {
+                size[axis] = *value as u32;
+            }
+            Some(value) => {
+                return Err(Diagnostic::error(
+                    DiagnosticCode::InvalidWorkgroupSize,
+                    format!(
+                        "Invalid {} on {}: {:?} (expected an integer between 1 and {})",
+                        property, entry_node.id, value, MAX_WORKGROUP_SIZE[axis]
+                    ),
+                )
+                .with_node(entry_node.id.clone()));
+            }
+        }
+    }
+
+    let invocations = size.iter().product::<u32>();
+    if invocations > MAX_WORKGROUP_INVOCATIONS {
+        return Err(Diagnostic::error(
+            DiagnosticCode::InvalidWorkgroupSize,
+            format!(
+                "Workgroup size {}x{}x{} on {} has {} invocations (limit is {})",
+                size[0], size[1], size[2], entry_node.id, invocations, MAX_WORKGROUP_INVOCATIONS
+            ),
+        )
+        .with_node(entry_node.id.clone()));
+    }
+
+    Ok(size)
+}
@@ -0,0 +1,139 @@
+//! # Literal Formatting
+//!
+//! Formatting of node property constants as WGSL literals of the destination
+//! pin's type.
+
+use crate::types::{ScalarType, WgslType};
+
+/// Format a constant value as a WGSL literal of the given type
+///
+/// `value` is the raw constant as stored on the node: a number (`1`, `6.28`),
+/// a boolean, a component list (`1, 0, 0`, `[1, 0, 0]`, `vec3(1, 0, 0)`) or a
+/// hex color (`#ff8000`, `#ff800080`). Single numbers are splatted across
+/// vectors, and hex colors without alpha get an alpha of `1.0` in `vec4`s.
+///
+/// Returns a description of the problem if the value can't be represented.
+pub fn format_literal(value: &str, target: &WgslType) -> Result<String, String> {
+    let value = value.trim();
+
+    match target {
+        WgslType::Scalar(scalar) => format_scalar(value, *scalar),
+        WgslType::Vector(size, scalar) => {
+            let components = if let Some(hex) = value.strip_prefix('#') {
+                parse_hex_color(hex, *size, *scalar)?
+            } else {
+                split_components(value)
+            };
+
+            let formatted = components
+                .iter()
+                .map(|component| format_scalar(component, *scalar))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if formatted.len() == 1 || formatted.len() == *size as usize {
+                Ok(format!("{}({})", target, formatted.join(", ")))
+            } else {
+                Err(format!(
+                    "expected 1 or {} components for {}, found {}",
+                    size, target, formatted.len()
+                ))
+            }
+        }
+        _ => Err(format!("constants of type {} are not supported", target)),
+    }
+}
+
+/// Format a single scalar literal
+fn format_scalar(value: &str, scalar: ScalarType) -> Result<String, String> {
+    let value = value.trim();
+
+    match scalar {
+        ScalarType::Bool => match value {
+            "true" | "1" => Ok("true".to_string()),
+            "false" | "0" => Ok("false".to_string()),
+            _ => Err(format!("'{}' is not a bool", value)),
+        },
+        ScalarType::F32 => {
+            let number = parse_number(value)?;
+            let float = number as f32;
+            if !float.is_finite() {
+                return Err(format!("'{}' is out of range for f32", value));
+            }
+            // Debug formatting always includes a decimal point or exponent
+            Ok(format!("{:?}", float))
+        }
+        ScalarType::I32 => {
+            let number = parse_integer(value)?;
+            if number < i32::MIN as f64 || number > i32::MAX as f64 {
+                return Err(format!("'{}' is out of range for i32", value));
+            }
+            match number as i32 {
+                // `2147483648i` isn't representable, so the minimum can't be negated
+                i32::MIN => Ok(format!("i32({} - 1)", i32::MIN + 1)),
+                number => Ok(format!("{}i", number)),
+            }
+        }
+        ScalarType::U32 => {
+            let number = parse_integer(value)?;
+            if number < 0.0 || number > u32::MAX as f64 {
+                return Err(format!("'{}' is out of range for u32", value));
+            }
+            Ok(format!("{}u", number as u32))
+        }
+    }
+}
+
+/// Parse a number, accepting WGSL suffixes (`1.0f`, `2u`, `3i`)
+fn parse_number(value: &str) -> Result<f64, String> {
+    let digits = value.trim_end_matches(|c| matches!(c, 'f' | 'h' | 'i' | 'u'));
+    match digits.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => Err(format!("'{}' is not a number", value)),
+    }
+}
+
+/// Parse a number that must be a whole number
+fn parse_integer(value: &str) -> Result<f64, String> {
+    let number = parse_number(value)?;
+    if number.fract() != 0.0 {
+        return Err(format!("'{}' is not an integer", value));
+    }
+    Ok(number)
+}
+
+/// Split a component list, stripping brackets or a constructor prefix
+fn split_components(value: &str) -> Vec<String> {
+    let inner = match value.find('(') {
+        Some(open) if value.ends_with(')') => &value[open + 1..value.len() - 1],
+        _ => value
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .unwrap_or(value),
+    };
+
+    inner.split(',').map(|component| component.trim().to_string()).collect()
+}
+
+/// Parse a `rrggbb` or `rrggbbaa` hex color into float components
+fn parse_hex_color(hex: &str, size: u8, scalar: ScalarType) -> Result<Vec<String>, String> {
+    if scalar != ScalarType::F32 || !(size == 3 || size == 4) {
+        return Err(format!("hex colors require vec3<f32> or vec4<f32>, not vec{}<{}>", size, scalar));
+    }
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'#{}' is not a hex color", hex));
+    }
+
+    let mut components: Vec<String> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0))
+        .map(|channel| (channel as f32 / 255.0).to_string())
+        .collect();
+
+    match (components.len(), size) {
+        (3, 4) => components.push("1.0".to_string()),
+        (4, 3) => return Err(format!("'#{}' has an alpha channel but the target is vec3<f32>", hex)),
+        _ => {}
+    }
+
+    Ok(components)
+}
@@ -3,5 +3,12 @@
 //! WGSL shader code generation from node graphs.
 
 mod wgsl_codegen;
+pub(crate) mod interface;
+mod template;
+mod helpers;
+mod origins;
+pub(crate) mod literals;
 
 pub use wgsl_codegen::*;
+pub use interface::*;
+pub use origins::GeneratedCode;
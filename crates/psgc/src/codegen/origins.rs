@@ -0,0 +1,164 @@
+//! # Line Origins
+//!
+//! Tracks which graph node and pin each part of the generated code comes from.
+//!
+//! Code generation precedes the statements of a node with a marker line.
+//! A marker applies to the lines that follow it at the same or a deeper
+//! indentation, until another marker replaces it, so the closing brace of
+//! a block belongs to the node that opened it. Expressions are wrapped in
+//! inline markers naming their node and pin, made of private-use characters
+//! that never occur in WGSL. Markers are removed once the code is complete,
+//! and line markers optionally turned into `// node_id (node_type)` comments.
+
+use crate::diagnostics::SourceSpan;
+use crate::source_map::{SourceMap, SourceMapEntry, SpanKind};
+
+/// Prefix of a marker line, followed by the node type and ID
+const MARKER: &str = "//@node ";
+
+/// Opens an inline marker, followed by the node ID, `SPAN_SEPARATOR`,
+/// the pin ID and `SPAN_SEPARATOR`
+const SPAN_OPEN: char = '\u{E000}';
+
+/// Ends the node and pin IDs of an inline marker
+const SPAN_SEPARATOR: char = '\u{E001}';
+
+/// Closes an inline marker
+const SPAN_CLOSE: char = '\u{E002}';
+
+/// Generated WGSL and where in the graph each part of it comes from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeneratedCode {
+    pub wgsl: String,
+    pub source_map: SourceMap,
+}
+
+impl GeneratedCode {
+    /// ID of the node that generated the given line (1-based), if any
+    pub fn node_at_line(&self, line: usize) -> Option<&str> {
+        self.source_map.node_at_line(line as u32)
+    }
+}
+
+/// Marker attributing the following lines to a node
+pub(crate) fn marker(node_id: &str, node_type: &str, indent: &str) -> String {
+    format!("{}{}{} {}\n", indent, MARKER, node_type, node_id)
+}
+
+/// Wrap an expression in an inline marker naming the node and pin it comes from
+pub(crate) fn span(node_id: &str, pin_id: &str, expr: &str) -> String {
+    format!(
+        "{}{}{}{}{}{}{}",
+        SPAN_OPEN, node_id, SPAN_SEPARATOR, pin_id, SPAN_SEPARATOR, expr, SPAN_CLOSE
+    )
+}
+
+/// An expression with its inline markers removed
+pub(crate) fn strip(expr: &str) -> String {
+    let mut stripped = String::with_capacity(expr.len());
+    let mut chars = expr.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            SPAN_OPEN => {
+                // Skip the node and pin IDs
+                chars.by_ref().take_while(|c| *c != SPAN_SEPARATOR).for_each(drop);
+                chars.by_ref().take_while(|c| *c != SPAN_SEPARATOR).for_each(drop);
+            }
+            SPAN_CLOSE => {}
+            c => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+/// Remove the markers from generated code, recording where each part comes from
+///
+/// With `annotate`, line markers are kept as `// node_id (node_type)` comments.
+pub(crate) fn resolve(code: &str, annotate: bool) -> GeneratedCode {
+    let mut generated = GeneratedCode {
+        wgsl: String::with_capacity(code.len()),
+        source_map: SourceMap::default(),
+    };
+    // Active line markers as (indentation, node ID), innermost last
+    let mut active: Vec<(usize, &str)> = Vec::new();
+    let mut line_number = 0;
+
+    for line in code.lines() {
+        let content = line.trim_start();
+        let indent = line.len() - content.len();
+
+        if let Some(marker) = content.strip_prefix(MARKER) {
+            let (node_type, node_id) = marker.split_once(' ').unwrap_or((marker, ""));
+            active.retain(|(marker_indent, _)| *marker_indent < indent);
+            active.push((indent, node_id));
+            if annotate {
+                generated.wgsl.push_str(&format!("{}// {} ({})\n", &line[..indent], node_id, node_type));
+                line_number += 1;
+            }
+            continue;
+        }
+
+        line_number += 1;
+        let (text, mut expressions) = resolve_line(line, line_number);
+
+        if !content.is_empty() {
+            active.retain(|(marker_indent, _)| *marker_indent <= indent);
+            if let Some((_, node_id)) = active.last() {
+                let trimmed = text.trim_start();
+                generated.source_map.entries.push(SourceMapEntry {
+                    span: SourceSpan {
+                        line: line_number,
+                        column: (text.len() - trimmed.len()) as u32 + 1,
+                        length: trimmed.trim_end().len() as u32,
+                    },
+                    kind: SpanKind::Statement,
+                    node_id: node_id.to_string(),
+                    pin_id: None,
+                });
+            }
+        }
+
+        expressions.sort_by_key(|entry| (entry.span.column, std::cmp::Reverse(entry.span.length)));
+        generated.source_map.entries.extend(expressions);
+        generated.wgsl.push_str(&text);
+        generated.wgsl.push('\n');
+    }
+
+    generated
+}
+
+/// Remove the inline markers from a line, returning it and the expressions they delimited
+fn resolve_line(line: &str, line_number: u32) -> (String, Vec<SourceMapEntry>) {
+    let mut text = String::with_capacity(line.len());
+    let mut expressions = Vec::new();
+    // Open markers as (node ID, pin ID, byte offset in `text`), innermost last
+    let mut open: Vec<(String, String, usize)> = Vec::new();
+
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            SPAN_OPEN => {
+                let node_id: String = chars.by_ref().take_while(|c| *c != SPAN_SEPARATOR).collect();
+                let pin_id: String = chars.by_ref().take_while(|c| *c != SPAN_SEPARATOR).collect();
+                open.push((node_id, pin_id, text.len()));
+            }
+            SPAN_CLOSE => {
+                if let Some((node_id, pin_id, start)) = open.pop() {
+                    expressions.push(SourceMapEntry {
+                        span: SourceSpan {
+                            line: line_number,
+                            column: start as u32 + 1,
+                            length: (text.len() - start) as u32,
+                        },
+                        kind: SpanKind::Expression,
+                        node_id,
+                        pin_id: Some(pin_id),
+                    });
+                }
+            }
+            c => text.push(c),
+        }
+    }
+
+    (text, expressions)
+}
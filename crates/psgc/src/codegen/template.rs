@@ -0,0 +1,197 @@
+//! # Source Templates
+//!
+//! Substitution of argument expressions into node source templates.
+//!
+//! Every wgsl_std node carries its WGSL body as a source template written in
+//! terms of its param names (e.g. `add` is `"a + b"`). Emitting a node means
+//! replacing each param identifier with the expression wired into that param.
+
+use super::origins;
+
+/// Substitute argument expressions into a node source template
+///
+/// `args` maps param names to the WGSL expressions bound to them. Identifiers
+/// preceded by `.` are member accesses and are never substituted, so a param
+/// named `x` doesn't clobber `v.x`. Arguments that aren't atomic expressions
+/// are wrapped in parentheses to preserve operator precedence, unless the
+/// param is a whole call argument, e.g. `x` in `sin(x)`.
+pub fn substitute(template: &str, args: &[(&str, &str)]) -> String {
+    rewrite_identifiers(template, |ident, is_call_argument| {
+        args.iter()
+            .find(|(name, _)| *name == ident)
+            .map(|(_, expr)| if is_call_argument { expr.trim().to_string() } else { parenthesize(expr) })
+    })
+}
+
+/// Count how many times a param is referenced by a source template
+pub fn count_uses(template: &str, param: &str) -> usize {
+    let mut count = 0;
+    rewrite_identifiers(template, |ident, _| {
+        if ident == param {
+            count += 1;
+        }
+        None
+    });
+    count
+}
+
+/// Rewrite the free identifiers of a template
+///
+/// `rewrite` is called for every identifier that isn't a member access or part
+/// of a numeric literal, along with whether the identifier is a whole call
+/// argument; returning `Some` replaces the identifier.
+fn rewrite_identifiers(template: &str, mut rewrite: impl FnMut(&str, bool) -> Option<String>) -> String {
+    let chars: Vec<char> = template.chars().collect();
+    let mut output = String::with_capacity(template.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_ascii_digit() {
+            // Numeric literal, including suffixes like `1u` or `1e-3`
+            let start = i;
+            while i < chars.len() && is_literal_char(&chars, i) {
+                i += 1;
+            }
+            output.extend(&chars[start..i]);
+        } else if is_ident_start(c) {
+            let start = i;
+            while i < chars.len() && is_ident_char(chars[i]) {
+                i += 1;
+            }
+            let ident: String = chars[start..i].iter().collect();
+
+            let is_member = previous_non_space(&chars, start) == Some('.');
+            let is_call_argument = matches!(previous_non_space(&chars, start), Some('(' | ','))
+                && matches!(next_non_space(&chars, i), Some(')' | ','));
+            let replacement = if is_member { None } else { rewrite(&ident, is_call_argument) };
+            output.push_str(replacement.as_deref().unwrap_or(&ident));
+        } else {
+            output.push(c);
+            i += 1;
+        }
+    }
+
+    output
+}
+
+/// Wrap an expression in parentheses unless it is atomic
+pub fn parenthesize(expr: &str) -> String {
+    if is_atomic(expr) {
+        expr.trim().to_string()
+    } else {
+        format!("({})", expr.trim())
+    }
+}
+
+/// Whether an expression can be used as an operand without parentheses
+///
+/// Atomic expressions are identifiers, numeric literals, calls and
+/// constructors (`f(...)`, `vec3<f32>(...)`), fully parenthesized
+/// expressions, and any of those followed by member, swizzle or index access.
+pub fn is_atomic(expr: &str) -> bool {
+    let chars: Vec<char> = origins::strip(expr).trim().chars().collect();
+    if chars.is_empty() {
+        return false;
+    }
+
+    let mut i = 0;
+    if chars[0] == '(' {
+        match matching_close(&chars, 0, '(', ')') {
+            Some(end) => i = end + 1,
+            None => return false,
+        }
+    } else if chars[0].is_ascii_digit() {
+        while i < chars.len() && is_literal_char(&chars, i) {
+            i += 1;
+        }
+        return i == chars.len();
+    } else if is_ident_start(chars[0]) {
+        while i < chars.len() && is_ident_char(chars[i]) {
+            i += 1;
+        }
+        // Template arguments of a type constructor, e.g. `vec3<f32>(...)`
+        if i < chars.len() && chars[i] == '<' {
+            match matching_close(&chars, i, '<', '>') {
+                Some(end) if chars.get(end + 1) == Some(&'(') => i = end + 1,
+                _ => return false,
+            }
+        }
+        if i < chars.len() && chars[i] == '(' {
+            match matching_close(&chars, i, '(', ')') {
+                Some(end) => i = end + 1,
+                None => return false,
+            }
+        }
+    } else {
+        return false;
+    }
+
+    // Postfix member, swizzle and index accesses
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                i += 1;
+                let start = i;
+                while i < chars.len() && is_ident_char(chars[i]) {
+                    i += 1;
+                }
+                if i == start {
+                    return false;
+                }
+            }
+            '[' => match matching_close(&chars, i, '[', ']') {
+                Some(end) => i = end + 1,
+                None => return false,
+            },
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+/// Find the index of the bracket closing the one opened at `open_index`
+fn matching_close(chars: &[char], open_index: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &c) in chars.iter().enumerate().skip(open_index) {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Last non-whitespace character before `index`
+fn previous_non_space(chars: &[char], index: usize) -> Option<char> {
+    chars[..index].iter().rev().find(|c| !c.is_whitespace()).copied()
+}
+
+/// First non-whitespace character at or after `index`
+fn next_non_space(chars: &[char], index: usize) -> Option<char> {
+    chars[index..].iter().find(|c| !c.is_whitespace()).copied()
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Whether `chars[index]` continues a numeric literal
+fn is_literal_char(chars: &[char], index: usize) -> bool {
+    let c = chars[index];
+    if c.is_ascii_alphanumeric() || c == '.' {
+        return true;
+    }
+    // Exponent sign, e.g. `1e-3`
+    (c == '-' || c == '+') && index > 0 && matches!(chars[index - 1], 'e' | 'E')
+}
@@ -2,30 +2,102 @@
 //!
 //! Generates WGSL shader code from node graphs.
 
+use super::interface::{
+    generate_vertex_output_struct, workgroup_size, ComputeBuiltin, FragmentInput, Varying,
+    VertexInput, COMPUTE_BUILTINS, FRAGMENT_INPUTS, VARYINGS, VERTEX_INPUTS,
+};
+use super::origins::{self, GeneratedCode};
+use super::{helpers, literals, template};
+use crate::diagnostics::{self, Diagnostic, DiagnosticCode};
 use crate::metadata::ShaderMetadataProvider;
+use crate::options::{CompileOptions, OptimizationLevel};
+use crate::reflection::{self, EntryPointReflection, ShaderReflection, VertexInputReflection};
+use crate::types::{self, GenericBindings, WgslType};
+use crate::typecheck::{ConversionKind, ImplicitConversion, TypeCheckReport};
+use crate::uniforms;
+use crate::variables;
 use graphy::{
-    GraphDescription, GraphyError, NodeTypes, NodeInstance,
+    GraphDescription, NodeTypes, NodeInstance,
     DataResolver, ExecutionRouting,
 };
 use graphy::core::NodeMetadataProvider;
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Upper bound on the iterations of a `for_loop` node
+pub const MAX_LOOP_ITERATIONS: u32 = 1024;
+
+/// Estimated cost from which a pure node read only once is still bound to a `let`
+const HOIST_COST_THRESHOLD: usize = 8;
+
+/// Estimated cost of a node taking a texture or sampler, i.e. a texture fetch
+const SAMPLE_COST: usize = 4;
 
 /// Shader stage type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ShaderStage {
     Vertex,
     Fragment,
     Compute,
 }
 
+impl ShaderStage {
+    /// Lowercase stage name, as used by `ShaderNodeInfo::stages`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Vertex => "vertex",
+            Self::Fragment => "fragment",
+            Self::Compute => "compute",
+        }
+    }
+
+    /// Node type of the stage's entry point, also the default function name
+    pub fn entry_point(&self) -> &'static str {
+        match self {
+            Self::Vertex => "vertex_main",
+            Self::Fragment => "fragment_main",
+            Self::Compute => "compute_main",
+        }
+    }
+
+    /// Node type the stage's results are wired into, if it has one
+    pub fn output_node(&self) -> Option<&'static str> {
+        match self {
+            Self::Vertex => Some("vertex_output"),
+            Self::Fragment => Some("fragment_output"),
+            Self::Compute => None,
+        }
+    }
+}
+
+/// How the fragment entry point receives its inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FragmentInterface {
+    /// Each input is a separate entry point parameter
+    Parameters,
+    /// Inputs are read from the vertex stage's `VertexOutput` struct
+    VertexOutput,
+}
+
 /// WGSL shader code generator
 pub struct WGSLCodeGenerator<'a> {
     graph: &'a GraphDescription,
     metadata_provider: &'a ShaderMetadataProvider,
     data_resolver: &'a DataResolver,
     exec_routing: &'a ExecutionRouting,
+    type_report: &'a TypeCheckReport,
+    options: &'a CompileOptions,
     stage: ShaderStage,
     visited: HashSet<String>,
+    /// Nodes whose result `let` is visible, one entry per open block: non-pure
+    /// nodes, and the hoisted pure nodes bound so far
+    scopes: Vec<Vec<String>>,
+    /// Pure nodes bound to a `let` at their first use instead of being
+    /// inlined, with the name of the `let`
+    hoisted: BTreeMap<String, String>,
+    /// Errors of the nodes generated so far; a broken node doesn't stop the
+    /// nodes after it from being generated
+    errors: Vec<Diagnostic>,
 }
 
 impl<'a> WGSLCodeGenerator<'a> {
@@ -34,6 +106,8 @@ impl<'a> WGSLCodeGenerator<'a> {
         metadata_provider: &'a ShaderMetadataProvider,
         data_resolver: &'a DataResolver,
         exec_routing: &'a ExecutionRouting,
+        type_report: &'a TypeCheckReport,
+        options: &'a CompileOptions,
         stage: ShaderStage,
     ) -> Self {
         Self {
@@ -41,100 +115,403 @@ impl<'a> WGSLCodeGenerator<'a> {
             metadata_provider,
             data_resolver,
             exec_routing,
+            type_report,
+            options,
             stage,
             visited: HashSet::new(),
+            scopes: vec![Vec::new()],
+            hoisted: BTreeMap::new(),
+            errors: Vec::new(),
         }
     }
 
     /// Generate complete WGSL shader
-    pub fn generate_shader(&self) -> Result<String, GraphyError> {
-        let mut code = String::new();
+    ///
+    /// Reports the errors of every node of every entry function.
+    pub fn generate_shader(&self) -> Result<GeneratedCode, Vec<Diagnostic>> {
+        let mut code = self.generate_header();
+        code.push_str(&self.generate_helper_functions(&[self.stage]));
+        code.push_str(&self.generate_material_bindings());
 
-        // Add header
-        code.push_str("// Auto-generated WGSL shader from Pulsar Shader Graph\n");
-        code.push_str("// DO NOT EDIT - Changes will be overwritten\n");
-        code.push_str("// Compiled with PSGC (Pulsar Shader Graph Compiler)\n\n");
+        // Vertex shaders return a struct carrying position and custom varyings
+        if self.stage == ShaderStage::Vertex {
+            let output_node = self.find_output_node("vertex_output").map_err(|error| vec![error])?;
+            let varyings = self.connected_varyings(output_node);
+            code.push_str(&generate_vertex_output_struct(&varyings));
+            code.push_str("\n");
+        }
 
-        // Find entry point based on stage
-        let entry_node_type = match self.stage {
-            ShaderStage::Vertex => "vertex_main",
-            ShaderStage::Fragment => "fragment_main",
-            ShaderStage::Compute => "compute_main",
+        code.push_str(&self.generate_stage(self.stage, FragmentInterface::Parameters)?);
+
+        Ok(origins::resolve(&code, self.options.debug_annotations))
+    }
+
+    /// Generate a WGSL module containing both the vertex and fragment entry points
+    ///
+    /// The fragment stage reads its inputs from the `VertexOutput` struct, so every
+    /// fragment input node must have a matching varying wired into `vertex_output`.
+    ///
+    /// Reports every missing varying and the errors of every node of every entry function.
+    pub fn generate_module(&self) -> Result<GeneratedCode, Vec<Diagnostic>> {
+        let mut code = self.generate_header();
+        code.push_str(&self.generate_helper_functions(&[ShaderStage::Vertex, ShaderStage::Fragment]));
+        code.push_str(&self.generate_material_bindings());
+
+        let output_node = self.find_output_node("vertex_output").map_err(|error| vec![error])?;
+        let varyings = self.connected_varyings(output_node);
+        let mut errors = self.check_fragment_inputs(&varyings);
+
+        code.push_str(&generate_vertex_output_struct(&varyings));
+        code.push_str("\n");
+        for stage in [ShaderStage::Vertex, ShaderStage::Fragment] {
+            match self.generate_stage(stage, FragmentInterface::VertexOutput) {
+                Ok(stage_code) => code.push_str(&stage_code),
+                Err(stage_errors) => errors.extend(stage_errors),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(origins::resolve(&code, self.options.debug_annotations))
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Describe the interface of the code generated for the given stages
+    pub fn generate_reflection(&self, stages: &[ShaderStage]) -> Result<ShaderReflection, Diagnostic> {
+        let mut entry_points = Vec::new();
+        for &stage in stages {
+            let workgroup_size = match stage {
+                ShaderStage::Compute => {
+                    let entry_node = self.nodes_of_type(stage.entry_point())
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| Diagnostic::error(
+                            DiagnosticCode::InvalidEntryPoint,
+                            format!("No {} entry point found in graph", stage.entry_point()),
+                        ))?;
+                    Some(workgroup_size(entry_node)?)
+                }
+                _ => None,
+            };
+            entry_points.push(EntryPointReflection {
+                name: self.options.entry_points.name(stage).to_string(),
+                stage,
+                workgroup_size,
+            });
+        }
+
+        let vertex_inputs = if stages.contains(&ShaderStage::Vertex) {
+            self.used_vertex_inputs()
+                .into_iter()
+                .map(VertexInputReflection::new)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(ShaderReflection {
+            entry_points,
+            bindings: reflection::material_bindings(
+                &self.type_report.material,
+                stages,
+                &self.parameter_stages(stages),
+            ),
+            vertex_inputs,
+        })
+    }
+
+    /// Generate the material uniform struct and resource bindings, if the graph has parameters
+    fn generate_material_bindings(&self) -> String {
+        let layout = &self.type_report.material;
+        if layout.is_empty() {
+            return String::new();
+        }
+
+        let mut code = String::new();
+        if self.options.comments {
+            code.push_str("// Material parameters\n");
+        }
+        code.push_str(&layout.generate_wgsl(self.options.comments));
+        code.push_str("\n");
+        code
+    }
+
+    /// Generate helper functions for the non-trivial node types used in the graph
+    ///
+    /// Generic node types get one helper per concrete instantiation. From
+    /// `OptimizationLevel::Basic` on, only nodes reachable from the entry
+    /// points of `stages` count as used.
+    fn generate_helper_functions(&self, stages: &[ShaderStage]) -> String {
+        let reachable = match self.options.optimization {
+            OptimizationLevel::None => None,
+            OptimizationLevel::Basic | OptimizationLevel::Full => Some(self.reachable_nodes(stages)),
         };
 
-        let entry_nodes: Vec<_> = self.graph
+        let mut used_helpers = BTreeMap::new();
+        for node in self.sorted_nodes() {
+            if reachable.as_ref().is_some_and(|reachable| !reachable.contains(node.id.as_str())) {
+                continue;
+            }
+            if let Some(node_meta) = self.metadata_provider.get_node_metadata(&node.node_type) {
+                if helpers::needs_helper(node_meta) {
+                    let bindings = self.generic_bindings(&node.id);
+                    used_helpers
+                        .entry(helpers::helper_name(&node_meta.name, &bindings))
+                        .or_insert((node_meta, bindings));
+                }
+            }
+        }
+
+        let mut code = String::new();
+        for (node_meta, bindings) in used_helpers.values() {
+            code.push_str(&helpers::generate_helper_function(node_meta, bindings));
+            code.push_str("\n");
+        }
+
+        code
+    }
+
+    /// Generate the file header comment and `enable` directives
+    fn generate_header(&self) -> String {
+        let mut code = String::new();
+        if self.options.header {
+            code.push_str("// Auto-generated WGSL shader from Pulsar Shader Graph\n");
+            code.push_str("// DO NOT EDIT - Changes will be overwritten\n");
+            code.push_str("// Compiled with PSGC (Pulsar Shader Graph Compiler)\n\n");
+        }
+        if !self.options.features.is_empty() {
+            for feature in &self.options.features {
+                code.push_str(&format!("enable {};\n", feature));
+            }
+            code.push_str("\n");
+        }
+        code
+    }
+
+    /// IDs of the nodes executed or read by the entry points of the given stages
+    fn reachable_nodes(&self, stages: &[ShaderStage]) -> HashSet<&'a str> {
+        use graphy::analysis::DataSource;
+
+        let mut pending: Vec<&'a NodeInstance> = self.graph
             .nodes
             .values()
-            .filter(|node| node.node_type == entry_node_type)
+            .filter(|node| stages.iter().any(|stage| {
+                node.node_type == stage.entry_point()
+                    || stage.output_node() == Some(node.node_type.as_str())
+            }))
             .collect();
 
+        let mut reachable = HashSet::new();
+        while let Some(node) = pending.pop() {
+            if !reachable.insert(node.id.as_str()) {
+                continue;
+            }
+            let Some(node_meta) = self.metadata_provider.get_node_metadata(&node.node_type) else {
+                continue;
+            };
+
+            for exec_pin in &node_meta.exec_outputs {
+                for next_node_id in self.exec_routing.get_connected_nodes(&node.id, exec_pin) {
+                    pending.extend(self.graph.nodes.get(next_node_id));
+                }
+            }
+            for param in &node_meta.params {
+                if let Some(DataSource::Connection { source_node_id, .. }) =
+                    self.data_resolver.get_input_source(&node.id, &param.name)
+                {
+                    pending.extend(self.graph.nodes.get(source_node_id));
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Stages among `stages` whose entry function reads each parameter
+    fn parameter_stages(&self, stages: &[ShaderStage]) -> BTreeMap<&'a str, Vec<ShaderStage>> {
+        let mut parameter_stages: BTreeMap<&'a str, Vec<ShaderStage>> = BTreeMap::new();
+        for &stage in stages {
+            for node_id in self.reachable_nodes(&[stage]) {
+                let parameter = self.graph
+                    .nodes
+                    .get(node_id)
+                    .and_then(|node| uniforms::parameter_name(node, self.metadata_provider));
+                if let Some(parameter) = parameter {
+                    let read_by = parameter_stages.entry(parameter).or_default();
+                    if !read_by.contains(&stage) {
+                        read_by.push(stage);
+                    }
+                }
+            }
+        }
+        parameter_stages
+    }
+
+    /// Generate the entry function(s) for a single stage
+    fn generate_stage(
+        &self,
+        stage: ShaderStage,
+        fragment_interface: FragmentInterface,
+    ) -> Result<String, Vec<Diagnostic>> {
+        let mut code = String::new();
+        let mut errors = Vec::new();
+
+        // Find entry point based on stage
+        let entry_node_type = stage.entry_point();
+
+        let entry_nodes = self.nodes_of_type(entry_node_type);
+
         if entry_nodes.is_empty() {
-            return Err(GraphyError::CodeGeneration(format!(
-                "No {} entry point found in graph",
-                entry_node_type
-            )));
+            return Err(vec![Diagnostic::error(
+                DiagnosticCode::InvalidEntryPoint,
+                format!("No {} entry point found in graph", entry_node_type),
+            )]);
         }
 
         // Generate entry function
         for entry_node in entry_nodes {
-            let entry_code = self.generate_entry_function(entry_node)?;
-            code.push_str(&entry_code);
-            code.push_str("\n");
+            match self.generate_entry_function(entry_node, stage, fragment_interface) {
+                Ok(entry_code) => {
+                    code.push_str(&entry_code);
+                    code.push_str("\n");
+                }
+                Err(entry_errors) => errors.extend(entry_errors),
+            }
         }
 
-        Ok(code)
+        if errors.is_empty() {
+            Ok(code)
+        } else {
+            Err(errors)
+        }
     }
 
     /// Generate entry function
-    fn generate_entry_function(&self, entry_node: &NodeInstance) -> Result<String, GraphyError> {
-        let mut code = String::new();
+    ///
+    /// A node that fails to generate is reported and skipped, so one call
+    /// reports the errors of every node in the function.
+    fn generate_entry_function(
+        &self,
+        entry_node: &NodeInstance,
+        stage: ShaderStage,
+        fragment_interface: FragmentInterface,
+    ) -> Result<String, Vec<Diagnostic>> {
+        let mut generator = self.clone_with_new_visited();
+        generator.stage = stage;
+        generator.hoisted = generator.hoistable_nodes();
+
+        let result = generator.generate_entry_body(entry_node, stage, fragment_interface);
+        if let Err(error) = result.as_ref() {
+            generator.report(error.clone());
+        }
+        match result {
+            Ok(code) if generator.errors.is_empty() => Ok(code),
+            _ => Err(generator.errors),
+        }
+    }
+
+    /// Generate the signature, body and return statement of an entry function
+    fn generate_entry_body(
+        &mut self,
+        entry_node: &NodeInstance,
+        stage: ShaderStage,
+        fragment_interface: FragmentInterface,
+    ) -> Result<String, Diagnostic> {
+        let mut code = origins::marker(&entry_node.id, &entry_node.node_type, "");
 
         // Get entry metadata
         let metadata = self.metadata_provider
             .get_node_metadata(&entry_node.node_type)
-            .ok_or_else(|| GraphyError::NodeNotFound(entry_node.node_type.clone()))?;
+            .ok_or_else(|| unknown_node_type(entry_node))?;
 
         // Generate function signature based on stage
-        match self.stage {
+        match stage {
             ShaderStage::Vertex => {
                 code.push_str("@vertex\n");
-                code.push_str("fn vertex_main(\n");
+                code.push_str(&format!("fn {}(\n", self.options.entry_points.name(stage)));
                 code.push_str("    @builtin(vertex_index) vertex_index: u32,\n");
-                code.push_str(") -> @builtin(position) vec4<f32> {\n");
+                for input in self.used_vertex_inputs() {
+                    code.push_str(&format!(
+                        "    @location({}) {}: {},\n",
+                        input.location, input.node_type, input.wgsl_type
+                    ));
+                }
+                code.push_str(") -> VertexOutput {\n");
             }
             ShaderStage::Fragment => {
                 code.push_str("@fragment\n");
-                code.push_str("fn fragment_main(\n");
-                code.push_str("    @builtin(position) frag_coord: vec4<f32>,\n");
-                code.push_str(") -> @location(0) vec4<f32> {\n");
+                code.push_str(&format!("fn {}(\n", self.options.entry_points.name(stage)));
+                match fragment_interface {
+                    FragmentInterface::Parameters => {
+                        for input in self.used_fragment_inputs() {
+                            match input.varying() {
+                                Some(varying) => code.push_str(&format!(
+                                    "    @location({}) {}: {},\n",
+                                    varying.location, input.node_type, input.wgsl_type
+                                )),
+                                None => code.push_str(&format!(
+                                    "    @builtin(position) {}: {},\n",
+                                    input.node_type, input.wgsl_type
+                                )),
+                            }
+                        }
+                        code.push_str(") -> @location(0) vec4<f32> {\n");
+                    }
+                    FragmentInterface::VertexOutput => {
+                        code.push_str("    input: VertexOutput,\n");
+                        code.push_str(") -> @location(0) vec4<f32> {\n");
+                        for input in self.used_fragment_inputs() {
+                            code.push_str(&format!(
+                                "    let {} = input.{};\n",
+                                input.node_type, input.field
+                            ));
+                        }
+                    }
+                }
             }
             ShaderStage::Compute => {
-                code.push_str("@compute @workgroup_size(8, 8, 1)\n");
-                code.push_str("fn compute_main(\n");
-                code.push_str("    @builtin(global_invocation_id) global_id: vec3<u32>,\n");
+                let [x, y, z] = workgroup_size(entry_node)?;
+                code.push_str(&format!("@compute @workgroup_size({}, {}, {})\n", x, y, z));
+                code.push_str(&format!("fn {}(\n", self.options.entry_points.name(stage)));
+                for builtin in self.used_compute_builtins() {
+                    code.push_str(&format!(
+                        "    @builtin({}) {}: {},\n",
+                        builtin.node_type, builtin.node_type, builtin.wgsl_type
+                    ));
+                }
                 code.push_str(") {\n");
             }
         }
 
+        code.push_str(&self.generate_variable_declarations()?);
+
         // Generate body
         if let Some(body_pin) = metadata.exec_outputs.first() {
-            let connected = self.exec_routing.get_connected_nodes(&entry_node.id, body_pin);
-            for next_node_id in connected {
-                if let Some(next_node) = self.graph.nodes.get(next_node_id) {
-                    let mut generator = self.clone_with_new_visited();
-                    let node_code = generator.generate_node_chain(next_node, 1)?;
-                    code.push_str(&node_code);
-                }
-            }
+            code.push_str(&self.generate_exec_output(&entry_node.id, body_pin, 1)?);
         }
 
-        // Return statement based on stage
-        match self.stage {
+        // Return statement based on stage, with only function-level results in scope
+        match stage {
             ShaderStage::Vertex => {
-                code.push_str("    return vec4<f32>(0.0, 0.0, 0.0, 1.0);\n");
+                let output_node = self.find_output_node("vertex_output")?;
+                code.push_str(&self.hoist_inputs(output_node, 1)?);
+                let position = self.generate_input_expression(&output_node.id, "position")?;
+                code.push_str(&origins::marker(&output_node.id, &output_node.node_type, "    "));
+                code.push_str("    var output: VertexOutput;\n");
+                code.push_str(&format!("    output.position = {};\n", position));
+                for varying in self.connected_varyings(output_node) {
+                    let value = self.generate_input_expression(&output_node.id, varying.name)?;
+                    code.push_str(&format!("    output.{} = {};\n", varying.name, value));
+                }
+                code.push_str("    return output;\n");
             }
             ShaderStage::Fragment => {
-                code.push_str("    return vec4<f32>(1.0, 0.0, 1.0, 1.0);\n");
+                let output_node = self.find_output_node("fragment_output")?;
+                code.push_str(&self.hoist_inputs(output_node, 1)?);
+                let color = self.generate_input_expression(&output_node.id, "color")?;
+                code.push_str(&origins::marker(&output_node.id, &output_node.node_type, "    "));
+                code.push_str(&format!("    return {};\n", color));
             }
             ShaderStage::Compute => {}
         }
@@ -144,19 +521,473 @@ impl<'a> WGSLCodeGenerator<'a> {
         Ok(code)
     }
 
-    /// Generate node chain
-    fn generate_node_chain(&mut self, node: &NodeInstance, indent_level: usize) -> Result<String, GraphyError> {
+    /// Declare the graph's local variables at the top of an entry function
+    fn generate_variable_declarations(&self) -> Result<String, Diagnostic> {
         let mut code = String::new();
 
-        // Prevent infinite loops
-        if self.visited.contains(&node.id) {
+        for variable in self.type_report.variables.values() {
+            let initial_value = match &variable.initial_value {
+                Some(value) => literals::format_literal(value, &variable.var_type).map_err(|reason| {
+                    Diagnostic::error(
+                        DiagnosticCode::InvalidVariable,
+                        format!(
+                            "Invalid initial value for variable {} ({}): {}",
+                            variable.name, variable.var_type, reason
+                        ),
+                    )
+                })?,
+                None => variable.var_type.zero_value().unwrap_or_default(),
+            };
+
+            code.push_str(&format!(
+                "    var {}: {} = {};\n",
+                variable.name, variable.var_type, initial_value
+            ));
+        }
+
+        Ok(code)
+    }
+
+    /// Check that every fragment input read by the graph is written by the vertex stage
+    ///
+    /// Reports each fragment input node without a matching varying.
+    fn check_fragment_inputs(&self, varyings: &[&Varying]) -> Vec<Diagnostic> {
+        let mut errors = Vec::new();
+
+        for input in self.used_fragment_inputs() {
+            if input.is_builtin_position() || varyings.iter().any(|varying| varying.name == input.field) {
+                continue;
+            }
+
+            for node in self.nodes_of_type(input.node_type) {
+                let mut error = Diagnostic::error(
+                    DiagnosticCode::MissingVarying,
+                    format!(
+                        "Fragment input {} ({}) has no matching vertex output: connect vertex_output.{}",
+                        input.node_type, node.id, input.field
+                    ),
+                )
+                .with_node(node.id.clone());
+                if let Ok(output_node) = self.find_output_node("vertex_output") {
+                    error = error.with_related_node(output_node.id.clone());
+                }
+                errors.push(error);
+            }
+        }
+
+        errors
+    }
+
+    /// Find the single output node of the given type
+    fn find_output_node(&self, output_node_type: &str) -> Result<&'a NodeInstance, Diagnostic> {
+        let mut output_nodes = self.nodes_of_type(output_node_type).into_iter();
+
+        let output_node = output_nodes.next().ok_or_else(|| {
+            Diagnostic::error(
+                DiagnosticCode::InvalidOutput,
+                format!("No {} node found in graph", output_node_type),
+            )
+        })?;
+
+        if let Some(duplicate) = output_nodes.next() {
+            return Err(Diagnostic::error(
+                DiagnosticCode::InvalidOutput,
+                format!(
+                    "Multiple {} nodes found in graph ({} and {})",
+                    output_node_type, output_node.id, duplicate.id
+                ),
+            )
+            .with_node(duplicate.id.clone())
+            .with_related_node(output_node.id.clone()));
+        }
+
+        Ok(output_node)
+    }
+
+    /// Custom varyings with a value wired into the vertex_output node
+    fn connected_varyings(&self, output_node: &NodeInstance) -> Vec<&'static Varying> {
+        use graphy::analysis::DataSource;
+
+        VARYINGS
+            .iter()
+            .filter(|varying| matches!(
+                self.data_resolver.get_input_source(&output_node.id, varying.name),
+                Some(DataSource::Connection { .. }) | Some(DataSource::Constant(_))
+            ))
+            .collect()
+    }
+
+    /// Vertex attributes read by input nodes in the graph
+    fn used_vertex_inputs(&self) -> Vec<&'static VertexInput> {
+        VERTEX_INPUTS
+            .iter()
+            .filter(|input| self.graph_uses_node_type(input.node_type))
+            .collect()
+    }
+
+    /// Fragment inputs read by input nodes in the graph
+    fn used_fragment_inputs(&self) -> Vec<&'static FragmentInput> {
+        FRAGMENT_INPUTS
+            .iter()
+            .filter(|input| self.graph_uses_node_type(input.node_type))
+            .collect()
+    }
+
+    /// Compute builtins read by input nodes in the graph
+    fn used_compute_builtins(&self) -> Vec<&'static ComputeBuiltin> {
+        COMPUTE_BUILTINS
+            .iter()
+            .filter(|builtin| self.graph_uses_node_type(builtin.node_type))
+            .collect()
+    }
+
+    /// Nodes of the graph in ID order
+    fn sorted_nodes(&self) -> Vec<&'a NodeInstance> {
+        let mut nodes: Vec<_> = self.graph.nodes.values().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        nodes
+    }
+
+    /// Nodes of the given type in ID order
+    fn nodes_of_type(&self, node_type: &str) -> Vec<&'a NodeInstance> {
+        let mut nodes = self.sorted_nodes();
+        nodes.retain(|node| node.node_type == node_type);
+        nodes
+    }
+
+    /// Whether any node in the graph has the given type
+    fn graph_uses_node_type(&self, node_type: &str) -> bool {
+        self.graph.nodes.values().any(|node| node.node_type == node_type)
+    }
+
+    /// Generate the chains of all nodes connected to an execution output
+    fn generate_exec_output(
+        &mut self,
+        node_id: &str,
+        exec_pin: &str,
+        indent_level: usize,
+    ) -> Result<String, Diagnostic> {
+        let mut code = String::new();
+
+        let connected = self.exec_routing.get_connected_nodes(node_id, exec_pin);
+        for next_node in self.exec_order(connected) {
+            match self.generate_node_chain(next_node, indent_level) {
+                Ok(chain) => code.push_str(&chain),
+                Err(error) => self.report(error),
+            }
+        }
+
+        Ok(code)
+    }
+
+    /// Record the error of a node and carry on generating
+    ///
+    /// Both arms of a branch may generate the same node, so an error already
+    /// recorded isn't recorded again.
+    fn report(&mut self, error: Diagnostic) {
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    /// Order the nodes connected to an execution output by topology, then ID
+    ///
+    /// A node reading the result of another node on the same output runs
+    /// after it; independent nodes run in ID order, so the generated code
+    /// doesn't depend on the order of the graph's connections.
+    fn exec_order<I, S>(&self, node_ids: I) -> Vec<&'a NodeInstance>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut pending: BTreeMap<&'a str, &'a NodeInstance> = node_ids
+            .into_iter()
+            .filter_map(|node_id| self.graph.nodes.get(node_id.as_ref()))
+            .map(|node| (node.id.as_str(), node))
+            .collect();
+        let dependencies: BTreeMap<&'a str, BTreeSet<String>> = pending
+            .keys()
+            .map(|&node_id| (node_id, self.data_dependencies(node_id)))
+            .collect();
+
+        let mut ordered = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            // Fall back to ID order if the nodes depend on each other
+            let next = pending
+                .keys()
+                .copied()
+                .find(|node_id| {
+                    dependencies[node_id]
+                        .iter()
+                        .all(|dependency| !pending.contains_key(dependency.as_str()))
+                })
+                .or_else(|| pending.keys().next().copied());
+            if let Some(node) = next.and_then(|node_id| pending.remove(node_id)) {
+                ordered.push(node);
+            }
+        }
+
+        ordered
+    }
+
+    /// IDs of the nodes whose outputs a node reads, directly or through other nodes
+    fn data_dependencies(&self, node_id: &str) -> BTreeSet<String> {
+        use graphy::analysis::DataSource;
+
+        let mut dependencies = BTreeSet::new();
+        let mut pending = vec![node_id.to_string()];
+        while let Some(node_id) = pending.pop() {
+            let Some(node_meta) = self.graph
+                .nodes
+                .get(&node_id)
+                .and_then(|node| self.metadata_provider.get_node_metadata(&node.node_type))
+            else {
+                continue;
+            };
+
+            for param in &node_meta.params {
+                if let Some(DataSource::Connection { source_node_id, .. }) =
+                    self.data_resolver.get_input_source(&node_id, &param.name)
+                {
+                    if dependencies.insert(source_node_id.clone()) {
+                        pending.push(source_node_id.clone());
+                    }
+                }
+            }
+        }
+
+        dependencies
+    }
+
+    /// Pure nodes of the current stage to bind to a `let` instead of inlining,
+    /// with the names of their `let`s
+    ///
+    /// From `OptimizationLevel::Basic` on, a pure node is hoisted when more
+    /// than one input of the nodes reachable from the entry point reads it.
+    /// `OptimizationLevel::Full` also hoists nodes whose inlined expression
+    /// costs at least `HOIST_COST_THRESHOLD` with the nodes hoisted for being
+    /// read more than once counting as free. Nodes reading a variable, whose
+    /// value changes as the function runs, and nodes returning resources,
+    /// which can't be bound to a `let`, are always inlined.
+    fn hoistable_nodes(&self) -> BTreeMap<String, String> {
+        use graphy::analysis::DataSource;
+
+        if self.options.optimization == OptimizationLevel::None {
+            return BTreeMap::new();
+        }
+
+        let reachable = self.reachable_nodes(&[self.stage]);
+        let mut reads: BTreeMap<String, usize> = BTreeMap::new();
+        for node in self.sorted_nodes() {
+            if !reachable.contains(node.id.as_str()) {
+                continue;
+            }
+            let Some(node_meta) = self.metadata_provider.get_node_metadata(&node.node_type) else {
+                continue;
+            };
+            for param in &node_meta.params {
+                if let Some(DataSource::Connection { source_node_id, .. }) =
+                    self.data_resolver.get_input_source(&node.id, &param.name)
+                {
+                    *reads.entry(source_node_id.clone()).or_default() += 1;
+                }
+            }
+        }
+
+        let candidates: Vec<(&'a NodeInstance, usize)> = reads
+            .into_iter()
+            .filter_map(|(node_id, reads)| self.graph.nodes.get(&node_id).map(|node| (node, reads)))
+            .filter(|(node, _)| self.is_hoistable(node))
+            .collect();
+
+        let mut hoisted: BTreeSet<String> = candidates
+            .iter()
+            .filter(|(node, reads)| *reads > 1 && self.expression_cost(node, &BTreeSet::new()) > 0)
+            .map(|(node, _)| node.id.clone())
+            .collect();
+        if self.options.optimization >= OptimizationLevel::Full {
+            let expensive: Vec<String> = candidates
+                .iter()
+                .filter(|(node, reads)| {
+                    *reads == 1 && self.expression_cost(node, &hoisted) >= HOIST_COST_THRESHOLD
+                })
+                .map(|(node, _)| node.id.clone())
+                .collect();
+            hoisted.extend(expensive);
+        }
+
+        self.hoisted_names(hoisted)
+    }
+
+    /// Unique `let` names for the hoisted nodes, `value_` and the node ID
+    ///
+    /// Characters that can't appear in an identifier become `_`. Names already
+    /// taken by a node with a lower ID, or by a result variable, get a
+    /// numbered suffix.
+    fn hoisted_names(&self, hoisted: BTreeSet<String>) -> BTreeMap<String, String> {
+        let mut used: BTreeSet<String> = self
+            .sorted_nodes()
+            .into_iter()
+            .filter_map(|node| self.data_resolver.get_result_variable(&node.id))
+            .map(|name| name.to_string())
+            .collect();
+
+        hoisted
+            .into_iter()
+            .map(|node_id| {
+                let id: String = node_id
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                    .collect();
+                let base = format!("value_{}", id);
+                let mut name = base.clone();
+                let mut suffix = 2;
+                while used.contains(&name) {
+                    name = format!("{}_{}", base, suffix);
+                    suffix += 1;
+                }
+                used.insert(name.clone());
+                (node_id, name)
+            })
+            .collect()
+    }
+
+    /// Whether a pure node's value can be bound to a `let` and reused
+    fn is_hoistable(&self, node: &NodeInstance) -> bool {
+        let Some(node_meta) = self.metadata_provider.get_node_metadata(&node.node_type) else {
+            return false;
+        };
+        let returns_resource = types::resolved_return_type(node_meta, &self.generic_bindings(&node.id))
+            .is_some_and(|return_type| return_type.is_resource());
+
+        node_meta.node_type == NodeTypes::pure && !returns_resource && !self.reads_variable(node)
+    }
+
+    /// Whether a pure node's inlined expression reads a variable
+    fn reads_variable(&self, node: &NodeInstance) -> bool {
+        variables::variable_name(node, self.metadata_provider).is_some()
+            || self.pure_sources(node).into_iter().any(|source| self.reads_variable(source))
+    }
+
+    /// Estimated cost of evaluating a pure node's inlined expression
+    ///
+    /// Each node taking inputs costs 1, or `SAMPLE_COST` when it takes a
+    /// texture or sampler. Nodes without inputs only read a value and are
+    /// free, as are the `hoisted` sources, which are read from their `let`.
+    fn expression_cost(&self, node: &NodeInstance, hoisted: &BTreeSet<String>) -> usize {
+        let Some(node_meta) = self.metadata_provider.get_node_metadata(&node.node_type) else {
+            return 0;
+        };
+        if node_meta.params.is_empty() {
+            return 0;
+        }
+
+        let samples = node_meta.params.iter().any(|param| {
+            self.input_type(&node.id, &param.name)
+                .is_ok_and(|param_type| param_type.is_resource())
+        });
+        let cost = if samples { SAMPLE_COST } else { 1 };
+
+        cost + self.pure_sources(node)
+            .into_iter()
+            .filter(|source| !hoisted.contains(&source.id))
+            .map(|source| self.expression_cost(source, hoisted))
+            .sum::<usize>()
+    }
+
+    /// Pure nodes read by a node's inputs, once per input
+    fn pure_sources(&self, node: &NodeInstance) -> Vec<&'a NodeInstance> {
+        use graphy::analysis::DataSource;
+
+        let Some(node_meta) = self.metadata_provider.get_node_metadata(&node.node_type) else {
+            return Vec::new();
+        };
+        node_meta.params
+            .iter()
+            .filter_map(|param| match self.data_resolver.get_input_source(&node.id, &param.name) {
+                Some(DataSource::Connection { source_node_id, .. }) => self.graph.nodes.get(source_node_id),
+                _ => None,
+            })
+            .filter(|source| self.is_pure(source))
+            .collect()
+    }
+
+    /// Bind the hoisted pure nodes read by a node's inputs that aren't bound yet
+    ///
+    /// Returns the `let` statements to emit before the node's own statement.
+    /// Hoisted nodes are bound in the current block, so a node first read
+    /// inside a block is bound again if it's read after the block.
+    fn hoist_inputs(&mut self, node: &NodeInstance, indent_level: usize) -> Result<String, Diagnostic> {
+        let mut code = String::new();
+        if self.hoisted.is_empty() {
             return Ok(code);
         }
-        self.visited.insert(node.id.clone());
+
+        for source in self.pure_sources(node) {
+            if self.in_scope(&source.id) {
+                continue;
+            }
+
+            // Bind the hoisted nodes the source reads first
+            code.push_str(&self.hoist_inputs(source, indent_level)?);
+
+            if let Some(name) = self.hoisted.get(&source.id) {
+                let indent = "    ".repeat(indent_level);
+                let expr = self.generate_pure_node_expression(source)?;
+                code.push_str(&origins::marker(&source.id, &source.node_type, &indent));
+                code.push_str(&format!("{}let {} = {};\n", indent, name, expr));
+                self.declare(&source.id);
+            }
+        }
+
+        Ok(code)
+    }
+
+    /// Generate the chains connected to an execution output inside a new block scope
+    fn generate_block(
+        &mut self,
+        node_id: &str,
+        exec_pin: &str,
+        indent_level: usize,
+    ) -> Result<String, Diagnostic> {
+        self.scopes.push(Vec::new());
+        let code = self.generate_exec_output(node_id, exec_pin, indent_level);
+        self.scopes.pop();
+        code
+    }
+
+    /// Generate node chain
+    ///
+    /// Each node is generated once per entry function, the first time an
+    /// execution path reaches it. Only the two arms of a branch, which never
+    /// both run, may each generate the same node.
+    fn generate_node_chain(&mut self, node: &NodeInstance, indent_level: usize) -> Result<String, Diagnostic> {
+        if !self.visited.insert(node.id.clone()) {
+            return Ok(String::new());
+        }
+        self.generate_exec_node(node, indent_level)
+    }
+
+    /// Generate a node on the execution path and the nodes following it
+    fn generate_exec_node(&mut self, node: &NodeInstance, indent_level: usize) -> Result<String, Diagnostic> {
+        let code = String::new();
 
         let node_meta = self.metadata_provider
             .get_node_metadata(&node.node_type)
-            .ok_or_else(|| GraphyError::NodeNotFound(node.node_type.clone()))?;
+            .ok_or_else(|| unknown_node_type(node))?;
+
+        if let Some(info) = self.metadata_provider.get_node_info(&node.node_type) {
+            if !info.supports_stage(self.stage.name()) {
+                return Err(Diagnostic::error(
+                    DiagnosticCode::UnsupportedStage,
+                    format!(
+                        "Node {} ({}) can't be used in a {} shader",
+                        node.id, node.node_type, self.stage.name()
+                    ),
+                )
+                .with_node(node.id.clone()));
+            }
+        }
 
         match node_meta.node_type {
             NodeTypes::pure => {
@@ -182,65 +1013,161 @@ impl<'a> WGSLCodeGenerator<'a> {
         node: &NodeInstance,
         node_meta: &graphy::core::NodeMetadata,
         indent_level: usize,
-    ) -> Result<String, GraphyError> {
-        let mut code = String::new();
+    ) -> Result<String, Diagnostic> {
+        let mut code = match self.generate_function_statement(node, node_meta, indent_level) {
+            Ok(statement) => statement,
+            Err(error) => {
+                self.report(error);
+                // Nodes reading the result are generated against its name
+                if node_meta.return_type.is_some() {
+                    self.declare(&node.id);
+                }
+                String::new()
+            }
+        };
+
+        // Follow execution chain
+        if let Some(exec_out) = node_meta.exec_outputs.first() {
+            code.push_str(&self.generate_exec_output(&node.id, exec_out, indent_level)?);
+        }
+
+        Ok(code)
+    }
+
+    /// Generate the statement of a function node, binding its result if it returns one
+    fn generate_function_statement(
+        &mut self,
+        node: &NodeInstance,
+        node_meta: &graphy::core::NodeMetadata,
+        indent_level: usize,
+    ) -> Result<String, Diagnostic> {
+        let mut code = self.hoist_inputs(node, indent_level)?;
         let indent = "    ".repeat(indent_level);
 
-        // Collect arguments
-        let args = self.collect_arguments(node, node_meta)?;
+        // Expand the node's source template
+        let expr = self.expand_node_source(node, node_meta)?;
 
         // Check if this function returns a value
         let has_return = node_meta.return_type.is_some();
 
+        code.push_str(&origins::marker(&node.id, &node.node_type, &indent));
+
         if has_return {
             let result_var = self.data_resolver
                 .get_result_variable(&node.id)
-                .ok_or_else(|| GraphyError::Custom(format!("No result variable for node: {}", node.id)))?;
+                .ok_or_else(|| missing_result_variable(node))?;
 
-            code.push_str(&format!(
-                "{}let {} = {}({});\n",
-                indent,
-                result_var,
-                self.map_function_name(&node_meta.name),
-                args.join(", ")
-            ));
+            code.push_str(&format!("{}let {} = {};\n", indent, result_var, expr));
+            self.declare(&node.id);
         } else {
-            code.push_str(&format!(
-                "{}{}({});\n",
-                indent,
-                self.map_function_name(&node_meta.name),
-                args.join(", ")
-            ));
-        }
-
-        // Follow execution chain
-        if let Some(exec_out) = node_meta.exec_outputs.first() {
-            let connected = self.exec_routing.get_connected_nodes(&node.id, exec_out);
-            for next_node_id in connected {
-                if let Some(next_node) = self.graph.nodes.get(next_node_id) {
-                    let next_code = self.generate_node_chain(next_node, indent_level)?;
-                    code.push_str(&next_code);
-                }
-            }
+            code.push_str(&format!("{}{};\n", indent, expr));
         }
 
         Ok(code)
     }
 
     /// Generate control flow node
+    ///
+    /// `branch` becomes an `if`/`else` and `for_loop` a bounded `for` loop
+    /// whose index is only in scope inside the loop body.
     fn generate_control_flow_node(
         &mut self,
-        _node: &NodeInstance,
+        node: &NodeInstance,
         _node_meta: &graphy::core::NodeMetadata,
         indent_level: usize,
-    ) -> Result<String, GraphyError> {
+    ) -> Result<String, Diagnostic> {
         let indent = "    ".repeat(indent_level);
-        // Placeholder for control flow
-        Ok(format!("{}// Control flow node\n", indent))
+        let mut code = self.hoist_inputs(node, indent_level)?;
+        code.push_str(&origins::marker(&node.id, &node.node_type, &indent));
+
+        match node.node_type.as_str() {
+            "branch" => {
+                let condition = self.generate_input_expression(&node.id, "condition")?;
+                let visited_before = self.visited.clone();
+                code.push_str(&format!("{}if {} {{\n", indent, condition));
+                code.push_str(&self.generate_block(&node.id, "Then", indent_level + 1)?);
+                if !self.exec_routing.get_connected_nodes(&node.id, "Else").is_empty() {
+                    // Nodes generated in Then may be generated again in Else
+                    let visited_then = std::mem::replace(&mut self.visited, visited_before);
+                    code.push_str(&format!("{}}} else {{\n", indent));
+                    code.push_str(&self.generate_block(&node.id, "Else", indent_level + 1)?);
+                    self.visited.extend(visited_then);
+                }
+                code.push_str(&format!("{}}}\n", indent));
+            }
+            "for_loop" => {
+                let index = self.data_resolver
+                    .get_result_variable(&node.id)
+                    .ok_or_else(|| missing_result_variable(node))?
+                    .clone();
+                let count = self.loop_count(node)?;
+
+                code.push_str(&format!(
+                    "{}for (var {}: u32 = 0u; {} < {}; {} = {} + 1u) {{\n",
+                    indent, index, index, count, index, index
+                ));
+                self.scopes.push(vec![node.id.clone()]);
+                let body = self.generate_exec_output(&node.id, "Body", indent_level + 1);
+                self.scopes.pop();
+                code.push_str(&body?);
+                code.push_str(&format!("{}}}\n", indent));
+
+                code.push_str(&self.generate_exec_output(&node.id, "Completed", indent_level)?);
+            }
+            other => {
+                return Err(Diagnostic::error(
+                    DiagnosticCode::InvalidNodeDefinition,
+                    format!("Unsupported control flow node type {} ({})", other, node.id),
+                )
+                .with_node(node.id.clone()));
+            }
+        }
+
+        Ok(code)
+    }
+
+    /// Iteration count of a for loop, bounded by `MAX_LOOP_ITERATIONS`
+    ///
+    /// Constant counts above the limit are rejected, connected counts are
+    /// clamped to it.
+    fn loop_count(&self, node: &NodeInstance) -> Result<String, Diagnostic> {
+        use graphy::analysis::DataSource;
+
+        let count = self.generate_input_expression(&node.id, "count")?;
+
+        if let Some(DataSource::Connection { .. }) = self.data_resolver.get_input_source(&node.id, "count") {
+            return Ok(format!("min({}, {}u)", count, MAX_LOOP_ITERATIONS));
+        }
+
+        let literal = origins::strip(&count);
+        match literal.trim_end_matches('u').parse::<u32>() {
+            Ok(value) if value <= MAX_LOOP_ITERATIONS => Ok(count),
+            _ => Err(Diagnostic::error(
+                DiagnosticCode::InvalidLoopCount,
+                format!(
+                    "Loop count {} on {} must be a constant between 0 and {}",
+                    literal, node.id, MAX_LOOP_ITERATIONS
+                ),
+            )
+            .with_input(self.graph, &node.id, "count")),
+        }
+    }
+
+    /// Record that a node's result variable is declared in the current block
+    fn declare(&mut self, node_id: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(node_id.to_string());
+        }
+    }
+
+    /// Whether a node's result variable, or the `let` of a hoisted pure node,
+    /// is declared in an enclosing block
+    fn in_scope(&self, node_id: &str) -> bool {
+        self.scopes.iter().flatten().any(|declared| declared == node_id)
     }
 
     /// Collect arguments for a function call
-    fn collect_arguments(&self, node: &NodeInstance, node_meta: &graphy::core::NodeMetadata) -> Result<Vec<String>, GraphyError> {
+    fn collect_arguments(&self, node: &NodeInstance, node_meta: &graphy::core::NodeMetadata) -> Result<Vec<String>, Diagnostic> {
         let mut args = Vec::new();
 
         for param in &node_meta.params {
@@ -252,76 +1179,273 @@ impl<'a> WGSLCodeGenerator<'a> {
     }
 
     /// Generate expression for an input value
-    fn generate_input_expression(&self, node_id: &str, pin_name: &str) -> Result<String, GraphyError> {
+    fn generate_input_expression(&self, node_id: &str, pin_name: &str) -> Result<String, Diagnostic> {
         use graphy::analysis::DataSource;
 
         match self.data_resolver.get_input_source(node_id, pin_name) {
-            Some(DataSource::Connection { source_node_id, source_pin: _ }) => {
-                let source_node = self.graph.nodes.get(source_node_id)
-                    .ok_or_else(|| GraphyError::NodeNotFound(source_node_id.clone()))?;
-
-                // Check if source is pure - if so, inline it
-                if let Some(node_meta) = self.metadata_provider.get_node_metadata(&source_node.node_type) {
-                    if node_meta.node_type == NodeTypes::pure {
-                        return self.generate_pure_node_expression(source_node);
-                    }
-                }
+            Some(DataSource::Connection { source_node_id, source_pin }) => {
+                let expr = self.generate_source_expression(source_node_id, source_pin)?;
 
-                // Non-pure: use result variable
-                if let Some(var_name) = self.data_resolver.get_result_variable(source_node_id) {
-                    Ok(var_name.clone())
-                } else {
-                    Err(GraphyError::Custom(format!("No variable for source node: {}", source_node_id)))
+                // Apply the implicit conversion inserted by type checking
+                match self.type_report.conversion(node_id, pin_name) {
+                    Some(conversion) => Ok(Self::apply_conversion(conversion, &expr)),
+                    None => Ok(expr),
                 }
             }
-            Some(DataSource::Constant(value)) => Ok(value.clone()),
+            Some(DataSource::Constant(value)) => {
+                let param_type = self.input_type(node_id, pin_name)?;
+                let literal = literals::format_literal(value, &param_type).map_err(|reason| {
+                    Diagnostic::error(
+                        DiagnosticCode::InvalidConstant,
+                        format!("Invalid constant for {}.{} ({}): {}", node_id, pin_name, param_type, reason),
+                    )
+                    .with_input(self.graph, node_id, pin_name)
+                })?;
+                Ok(self.input_span(node_id, pin_name, &literal))
+            }
             Some(DataSource::Default) => {
-                Ok("0.0".to_string()) // WGSL default
+                let default = self.generate_default_value(node_id, pin_name)?;
+                Ok(self.input_span(node_id, pin_name, &default))
             }
-            None => Err(GraphyError::Custom(format!("No data source for input: {}.{}", node_id, pin_name))),
+            None => Err(Diagnostic::error(
+                DiagnosticCode::GraphAnalysis,
+                format!("No data source for input: {}.{}", node_id, pin_name),
+            )
+            .with_input(self.graph, node_id, pin_name)),
         }
     }
 
+    /// Generate the expression for the value read from a source node's output pin
+    ///
+    /// Named outputs and swizzle nodes read components of the node's value,
+    /// e.g. `frag_uv.x`.
+    fn generate_source_expression(&self, source_node_id: &str, source_pin: &str) -> Result<String, Diagnostic> {
+        let source_node = self.node(source_node_id)?;
+
+        let expr = if self.is_pure(source_node) {
+            match self.hoisted.get(source_node_id) {
+                // Hoisted pure sources read the `let` bound at their first use
+                Some(name) if self.in_scope(source_node_id) => name.clone(),
+                // Pure sources are inlined
+                _ => self.generate_pure_node_expression(source_node)?,
+            }
+        } else if let Some(var_name) = self.data_resolver.get_result_variable(source_node_id) {
+            // Non-pure: use result variable, which must be declared in an enclosing block
+            if !self.in_scope(source_node_id) {
+                return Err(Diagnostic::error(
+                    DiagnosticCode::OutOfScope,
+                    format!(
+                        "Result of {} is used outside the block it runs in (or before it runs)",
+                        source_node_id
+                    ),
+                )
+                .with_node(source_node_id));
+            }
+            var_name.clone()
+        } else {
+            return Err(missing_result_variable(source_node));
+        };
+
+        let swizzle = match self.metadata_provider.get_node_output(source_node, source_pin) {
+            Some(output) => Some(output.swizzle.clone()),
+            None => self.metadata_provider.get_swizzle_mask(source_node),
+        };
+
+        let expr = match swizzle {
+            Some(mask) => format!("{}.{}", template::parenthesize(&expr), mask),
+            None => expr,
+        };
+        Ok(origins::span(source_node_id, &output_pin_id(source_node, source_pin), &expr))
+    }
+
+    /// Mark an expression as the value of a node's input pin
+    fn input_span(&self, node_id: &str, pin_name: &str, expr: &str) -> String {
+        origins::span(node_id, &diagnostics::input_pin_id(self.graph, node_id, pin_name), expr)
+    }
+
+    /// Wrap an expression in an implicit conversion
+    fn apply_conversion(conversion: &ImplicitConversion, expr: &str) -> String {
+        match conversion.kind {
+            ConversionKind::Splat | ConversionKind::IntToFloat => {
+                format!("{}({})", conversion.to, expr)
+            }
+            ConversionKind::Truncate => {
+                let size = conversion.to.component_count().unwrap_or(4) as usize;
+                format!("{}.{}", template::parenthesize(expr), &"xyzw"[..size])
+            }
+            ConversionKind::AppendAlpha => format!("vec4<f32>({}, 1.0)", expr),
+        }
+    }
+
+    /// Generate the value of an unconnected input
+    ///
+    /// Uses the param default declared in wgsl_std, falling back to the zero
+    /// value of the param's type. Resource params have no zero value and must
+    /// be connected.
+    fn generate_default_value(&self, node_id: &str, pin_name: &str) -> Result<String, Diagnostic> {
+        let node = self.node(node_id)?;
+
+        let param_type = self.input_type(node_id, pin_name)?;
+
+        if let Some(default) = self.metadata_provider
+            .get_node_info(&node.node_type)
+            .and_then(|info| info.param_default(pin_name))
+        {
+            // Reformat literal defaults for the resolved type of generic params,
+            // anything else is used as written
+            return Ok(literals::format_literal(default, &param_type)
+                .unwrap_or_else(|_| default.to_string()));
+        }
+
+        param_type.zero_value().ok_or_else(|| {
+            Diagnostic::error(
+                DiagnosticCode::UnconnectedInput,
+                format!("Input {}.{} of type {} must be connected", node_id, pin_name, param_type),
+            )
+            .with_input(self.graph, node_id, pin_name)
+        })
+    }
+
+    /// Concrete types resolved for a node's generic type parameters
+    fn generic_bindings(&self, node_id: &str) -> GenericBindings {
+        self.type_report
+            .generic_bindings(node_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// WGSL type of a node input, from the node's param metadata
+    fn input_type(&self, node_id: &str, pin_name: &str) -> Result<WgslType, Diagnostic> {
+        let node = self.node(node_id)?;
+
+        let node_meta = self.metadata_provider
+            .get_node_metadata(&node.node_type)
+            .ok_or_else(|| unknown_node_type(node))?;
+
+        node_meta.params
+            .iter()
+            .find(|param| param.name == pin_name)
+            .map(|param| types::resolved_param_type(param, &self.generic_bindings(node_id)))
+            .ok_or_else(|| Diagnostic::error(
+                DiagnosticCode::InvalidNodeDefinition,
+                format!("Node type {} has no param {}", node.node_type, pin_name),
+            )
+            .with_node(node_id))
+    }
+
+    /// Node with the given ID
+    fn node(&self, node_id: &str) -> Result<&'a NodeInstance, Diagnostic> {
+        self.graph.nodes.get(node_id).ok_or_else(|| {
+            Diagnostic::error(
+                DiagnosticCode::GraphAnalysis,
+                format!("Node not found: {}", node_id),
+            )
+        })
+    }
+
+    /// Whether a node is pure, and so inlined where it's read
+    fn is_pure(&self, node: &NodeInstance) -> bool {
+        self.metadata_provider
+            .get_node_metadata(&node.node_type)
+            .is_some_and(|node_meta| node_meta.node_type == NodeTypes::pure)
+    }
+
     /// Generate inlined expression for a pure node
-    fn generate_pure_node_expression(&self, node: &NodeInstance) -> Result<String, GraphyError> {
+    fn generate_pure_node_expression(&self, node: &NodeInstance) -> Result<String, Diagnostic> {
         let node_meta = self.metadata_provider
             .get_node_metadata(&node.node_type)
-            .ok_or_else(|| GraphyError::NodeNotFound(node.node_type.clone()))?;
+            .ok_or_else(|| unknown_node_type(node))?;
+
+        self.expand_node_source(node, node_meta)
+    }
+
+    /// Expand a node's source template with the expressions bound to its params
+    fn expand_node_source(
+        &self,
+        node: &NodeInstance,
+        node_meta: &graphy::core::NodeMetadata,
+    ) -> Result<String, Diagnostic> {
+        let source = node_meta.function_source.trim();
+        if source.is_empty() {
+            return Err(Diagnostic::error(
+                DiagnosticCode::InvalidNodeDefinition,
+                format!("Node type {} has no WGSL source", node_meta.name),
+            )
+            .with_node(node.id.clone()));
+        }
 
         // Recursively generate arguments
-        let mut args = Vec::new();
-        for param in &node_meta.params {
-            let arg_expr = self.generate_input_expression(&node.id, &param.name)?;
-            args.push(arg_expr);
+        let args = self.collect_arguments(node, node_meta)?;
+
+        // Non-trivial nodes call their helper function from the prelude
+        if helpers::needs_helper(node_meta) {
+            let name = helpers::helper_name(&node_meta.name, &self.generic_bindings(&node.id));
+            return Ok(format!("{}({})", name, args.join(", ")));
         }
 
-        Ok(format!("{}({})", self.map_function_name(&node_meta.name), args.join(", ")))
-    }
+        let mut bindings: Vec<(&str, &str)> = node_meta
+            .params
+            .iter()
+            .zip(&args)
+            .map(|(param, arg)| (param.name.as_str(), arg.as_str()))
+            .collect();
+
+        // Variable nodes refer to their variable as `variable`
+        if let Some(name) = variables::variable_name(node, self.metadata_provider) {
+            bindings.push(("variable", name));
+        }
 
-    /// Map function names to WGSL built-ins
-    fn map_function_name(&self, name: &str) -> String {
-        match name {
-            "add" => "add",
-            "multiply" => "multiply",
-            "dot" => "dot",
-            "normalize" => "normalize",
-            "vec3" => "vec3<f32>",
-            "vec4" => "vec4<f32>",
-            "sample_texture" => "textureSample",
-            _ => name,
+        // Parameter nodes refer to their uniform field or resource as `parameter`
+        let parameter = uniforms::parameter_name(node, self.metadata_provider)
+            .and_then(|name| self.type_report.material.read_expression(name));
+        if let Some(expression) = &parameter {
+            bindings.push(("parameter", expression));
         }
-        .to_string()
+
+        Ok(template::substitute(source, &bindings))
     }
 
-    /// Clone with new visited set
+    /// Clone with new visited set and scopes
     fn clone_with_new_visited(&self) -> Self {
         Self {
             graph: self.graph,
             metadata_provider: self.metadata_provider,
             data_resolver: self.data_resolver,
             exec_routing: self.exec_routing,
+            type_report: self.type_report,
+            options: self.options,
             stage: self.stage,
             visited: HashSet::new(),
+            scopes: vec![Vec::new()],
+            hoisted: BTreeMap::new(),
+            errors: Vec::new(),
         }
     }
 }
+
+/// Error for a node whose type isn't in the shader node registry
+fn unknown_node_type(node: &NodeInstance) -> Diagnostic {
+    Diagnostic::error(
+        DiagnosticCode::UnknownNodeType,
+        format!("Unknown node type {} for node {}", node.node_type, node.id),
+    )
+    .with_node(node.id.clone())
+}
+
+/// ID of the output pin a connection reads from, which may be given by ID or name
+fn output_pin_id(node: &NodeInstance, source_pin: &str) -> String {
+    node.outputs
+        .iter()
+        .find(|output| output.id == source_pin || output.pin.name == source_pin)
+        .map(|output| output.id.clone())
+        .unwrap_or_else(|| source_pin.to_string())
+}
+
+/// Error for a non-pure node that data flow analysis gave no result variable
+fn missing_result_variable(node: &NodeInstance) -> Diagnostic {
+    Diagnostic::error(
+        DiagnosticCode::GraphAnalysis,
+        format!("No result variable for node: {}", node.id),
+    )
+    .with_node(node.id.clone())
+}
@@ -4,12 +4,39 @@
 
 use crate::metadata::ShaderMetadataProvider;
 use crate::codegen::{WGSLCodeGenerator, ShaderStage};
-use graphy::{GraphDescription, GraphyError, DataResolver, ExecutionRouting};
+use crate::declarations::GraphDeclarations;
+use crate::diagnostics::{CompileFailure, Diagnostic, DiagnosticCode};
+use crate::options::CompileOptions;
+use crate::reflection::CompiledShader;
+#[cfg(feature = "validation")]
+use crate::targets::{CompileTarget, TargetOutput};
+use crate::typecheck::{ConversionPolicy, TypeChecker};
+use graphy::{GraphDescription, DataResolver, ExecutionRouting};
 use graphy::core::NodeMetadataProvider;
 
-/// Compile a shader graph to WGSL code
+/// What a compilation run generates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompileOutput {
+    /// The entry point(s) of a single stage
+    Stage(ShaderStage),
+    /// Vertex and fragment entry points sharing a `VertexOutput` struct
+    Module,
+}
+
+impl CompileOutput {
+    /// Stages whose entry points are generated
+    fn stages(&self) -> Vec<ShaderStage> {
+        match self {
+            Self::Stage(stage) => vec![*stage],
+            Self::Module => vec![ShaderStage::Vertex, ShaderStage::Fragment],
+        }
+    }
+}
+
+/// Compile a shader graph to WGSL code and its reflection
 ///
-/// Automatically detects the shader stage from entry nodes.
+/// Automatically detects the shader stage from entry nodes. Graphs with both
+/// `vertex_main` and `fragment_main` are compiled into a single module.
 ///
 /// # Arguments
 ///
@@ -17,45 +44,148 @@ use graphy::core::NodeMetadataProvider;
 ///
 /// # Returns
 ///
-/// * `Ok(String)` - The generated WGSL source code
-/// * `Err(GraphyError)` - A descriptive error if compilation fails
-pub fn compile_shader(graph: &GraphDescription) -> Result<String, GraphyError> {
-    // Auto-detect stage from graph
+/// * `Ok(CompiledShader)` - The generated WGSL source code, its reflection
+///   and any warnings
+/// * `Err(CompileFailure)` - Every error (and warning) found in the graph
+pub fn compile_shader(graph: &GraphDescription) -> Result<CompiledShader, CompileFailure> {
+    compile_with_options(graph, &CompileOptions::default())
+}
+
+/// Compile a shader graph to WGSL code with a custom implicit conversion policy
+///
+/// Detects the shader stage the same way as [`compile_shader`].
+pub fn compile_shader_with_policy(
+    graph: &GraphDescription,
+    policy: &ConversionPolicy,
+) -> Result<CompiledShader, CompileFailure> {
+    let options = CompileOptions {
+        conversion_policy: *policy,
+        ..CompileOptions::default()
+    };
+    compile_with_options(graph, &options)
+}
+
+/// Compile a vertex shader
+pub fn compile_vertex_shader(graph: &GraphDescription) -> Result<CompiledShader, CompileFailure> {
+    compile_with_options(graph, &CompileOptions::for_stages(&[ShaderStage::Vertex]))
+}
+
+/// Compile a fragment shader
+pub fn compile_fragment_shader(graph: &GraphDescription) -> Result<CompiledShader, CompileFailure> {
+    compile_with_options(graph, &CompileOptions::for_stages(&[ShaderStage::Fragment]))
+}
+
+/// Compile a compute shader
+///
+/// The workgroup size is taken from the `compute_main` node's
+/// `workgroup_size_x/y/z` properties.
+pub fn compile_compute_shader(graph: &GraphDescription) -> Result<CompiledShader, CompileFailure> {
+    compile_with_options(graph, &CompileOptions::for_stages(&[ShaderStage::Compute]))
+}
+
+/// Compile a vertex and fragment shader into a single WGSL module
+///
+/// Both entry points share a generated `VertexOutput` struct. Every fragment
+/// input (`frag_uv`, `frag_normal`, ...) must be written by a matching
+/// `vertex_output` varying.
+pub fn compile_module(graph: &GraphDescription) -> Result<CompiledShader, CompileFailure> {
+    compile_with_options(
+        graph,
+        &CompileOptions::for_stages(&[ShaderStage::Vertex, ShaderStage::Fragment]),
+    )
+}
+
+/// Compile a shader graph with the given options
+///
+/// Generates the stages listed in `options.stages`, or detects them from the
+/// graph's entry nodes the same way as [`compile_shader`] when it's empty.
+pub fn compile_with_options(
+    graph: &GraphDescription,
+    options: &CompileOptions,
+) -> Result<CompiledShader, CompileFailure> {
+    compile_with_declarations(graph, &GraphDeclarations::default(), options)
+}
+
+/// Compile a shader graph with its graph-level declarations and the given options
+///
+/// `declarations` holds the local variables the graph's nodes refer to by
+/// name. Stages are chosen the same way as [`compile_with_options`].
+pub fn compile_with_declarations(
+    graph: &GraphDescription,
+    declarations: &GraphDeclarations,
+    options: &CompileOptions,
+) -> Result<CompiledShader, CompileFailure> {
+    let errors = options.validate();
+    if !errors.is_empty() {
+        return Err(CompileFailure::new(errors));
+    }
+
+    let output = if options.stages.is_empty() {
+        detect_output(graph)?
+    } else {
+        requested_output(&options.stages)?
+    };
+
+    compile_graph(graph, declarations, output, options)
+}
+
+/// Detect what to generate from the entry nodes in the graph
+fn detect_output(graph: &GraphDescription) -> Result<CompileOutput, Diagnostic> {
     let has_vertex = graph.nodes.values().any(|n| n.node_type == "vertex_main");
     let has_fragment = graph.nodes.values().any(|n| n.node_type == "fragment_main");
+    let has_compute = graph.nodes.values().any(|n| n.node_type == "compute_main");
 
-    if has_fragment {
-        compile_fragment_shader(graph)
+    if has_compute && (has_vertex || has_fragment) {
+        Err(Diagnostic::error(
+            DiagnosticCode::InvalidEntryPoint,
+            "Graph mixes compute_main with vertex_main/fragment_main entry points",
+        ))
+    } else if has_compute {
+        Ok(CompileOutput::Stage(ShaderStage::Compute))
+    } else if has_vertex && has_fragment {
+        Ok(CompileOutput::Module)
+    } else if has_fragment {
+        Ok(CompileOutput::Stage(ShaderStage::Fragment))
     } else if has_vertex {
-        compile_vertex_shader(graph)
+        Ok(CompileOutput::Stage(ShaderStage::Vertex))
     } else {
-        Err(GraphyError::CodeGeneration(
-            "No shader entry point found (vertex_main or fragment_main)".to_string(),
+        Err(Diagnostic::error(
+            DiagnosticCode::InvalidEntryPoint,
+            "No shader entry point found (vertex_main, fragment_main or compute_main)",
         ))
     }
 }
 
-/// Compile a vertex shader
-pub fn compile_vertex_shader(graph: &GraphDescription) -> Result<String, GraphyError> {
-    compile_shader_with_stage(graph, ShaderStage::Vertex)
-}
+/// What to generate for an explicit list of stages
+fn requested_output(stages: &[ShaderStage]) -> Result<CompileOutput, Diagnostic> {
+    use ShaderStage::{Fragment, Vertex};
 
-/// Compile a fragment shader
-pub fn compile_fragment_shader(graph: &GraphDescription) -> Result<String, GraphyError> {
-    compile_shader_with_stage(graph, ShaderStage::Fragment)
+    match stages {
+        [stage] => Ok(CompileOutput::Stage(*stage)),
+        [Vertex, Fragment] | [Fragment, Vertex] => Ok(CompileOutput::Module),
+        _ => Err(Diagnostic::error(
+            DiagnosticCode::InvalidOption,
+            format!(
+                "Can't compile stages {:?} together: request a single stage, or vertex and fragment",
+                stages
+            ),
+        )),
+    }
 }
 
-/// Compile a shader with a specific stage
-fn compile_shader_with_stage(
+/// Run the compilation pipeline
+fn compile_graph(
     graph: &GraphDescription,
-    stage: ShaderStage,
-) -> Result<String, GraphyError> {
+    declarations: &GraphDeclarations,
+    output: CompileOutput,
+    options: &CompileOptions,
+) -> Result<CompiledShader, CompileFailure> {
     tracing::info!("[PSGC] Starting shader compilation");
     tracing::info!("[PSGC] Graph: {} ({} nodes, {} connections)",
         graph.metadata.name,
         graph.nodes.len(),
         graph.connections.len());
-    tracing::info!("[PSGC] Stage: {:?}", stage);
+    tracing::info!("[PSGC] Output: {:?}", output);
 
     // Phase 1: Get shader metadata
     tracing::info!("[PSGC] Phase 1: Loading shader node metadata...");
@@ -65,27 +195,121 @@ fn compile_shader_with_stage(
 
     // Phase 2: Build data flow resolver
     tracing::info!("[PSGC] Phase 2: Analyzing data flow...");
-    let data_resolver = DataResolver::build(graph, &metadata_provider)?;
+    let data_resolver = DataResolver::build(graph, &metadata_provider)
+        .map_err(|e| Diagnostic::error(DiagnosticCode::GraphAnalysis, e.to_string()))?;
     tracing::info!("[PSGC] Data flow analysis complete");
 
-    // Phase 3: Build execution routing
-    tracing::info!("[PSGC] Phase 3: Analyzing execution flow...");
+    // Phase 3: Type check data connections
+    tracing::info!("[PSGC] Phase 3: Type checking...");
+    let type_report = TypeChecker::new(graph, &metadata_provider, &data_resolver)
+        .with_policy(options.conversion_policy)
+        .with_material_group(options.material_group)
+        .with_entry_points(options.entry_points.clone())
+        .with_declarations(declarations.clone())
+        .check();
+    let warnings: Vec<Diagnostic> = type_report
+        .warnings()
+        .filter_map(|conversion| Diagnostic::from_conversion(conversion, graph))
+        .collect();
+    for warning in &warnings {
+        tracing::warn!("[PSGC] {}", warning);
+    }
+    if !type_report.is_ok() {
+        let errors: Vec<Diagnostic> = type_report
+            .errors
+            .iter()
+            .map(|error| Diagnostic::from_type_error(error, graph))
+            .collect();
+        return Err(failure(errors, warnings));
+    }
+    tracing::info!("[PSGC] Type checking complete ({} implicit conversions)",
+        type_report.conversions.len());
+
+    // Phase 4: Build execution routing
+    tracing::info!("[PSGC] Phase 4: Analyzing execution flow...");
     let exec_routing = ExecutionRouting::build_from_graph(graph);
     tracing::info!("[PSGC] Execution flow analysis complete");
 
-    // Phase 4: Generate WGSL code
-    tracing::info!("[PSGC] Phase 4: Generating WGSL code...");
+    // Phase 5: Generate WGSL code
+    tracing::info!("[PSGC] Phase 5: Generating WGSL code...");
+    let stage = match output {
+        CompileOutput::Stage(stage) => stage,
+        CompileOutput::Module => ShaderStage::Vertex,
+    };
     let code_generator = WGSLCodeGenerator::new(
         graph,
         &metadata_provider,
         &data_resolver,
         &exec_routing,
+        &type_report,
+        options,
         stage,
     );
-    let code = code_generator.generate_shader()?;
+    let code = match output {
+        CompileOutput::Stage(_) => code_generator.generate_shader(),
+        CompileOutput::Module => code_generator.generate_module(),
+    };
+    let generated = code.map_err(|errors| failure(errors, warnings.clone()))?;
+    let reflection = code_generator
+        .generate_reflection(&output.stages())
+        .map_err(|error| failure(vec![error], warnings.clone()))?;
+
+    tracing::info!("[PSGC] Code generation complete ({} bytes, {} bindings)",
+        generated.wgsl.len(),
+        reflection.bindings.len());
+
+    // Phase 6: Validate the generated WGSL
+    #[cfg(feature = "validation")]
+    let targets = if options.validate || options.target != CompileTarget::Wgsl {
+        tracing::info!("[PSGC] Phase 6: Validating WGSL...");
+        let validated = crate::validation::validate(&generated)
+            .map_err(|errors| failure(errors, warnings.clone()))?;
+        tracing::info!("[PSGC] Validation complete");
+
+        translate(&validated, options.target)
+            .map_err(|error| failure(vec![error], warnings.clone()))?
+    } else {
+        Vec::new()
+    };
+    #[cfg(not(feature = "validation"))]
+    let targets = Vec::new();
 
-    tracing::info!("[PSGC] Code generation complete ({} bytes)", code.len());
     tracing::info!("[PSGC] Compilation successful!");
 
-    Ok(code)
+    Ok(CompiledShader {
+        wgsl: generated.wgsl,
+        reflection,
+        source_map: generated.source_map,
+        diagnostics: warnings,
+        targets,
+    })
+}
+
+/// Phase 7: Translate the validated module to the compile target
+#[cfg(feature = "cross-compile")]
+fn translate(
+    (module, info): &(naga::Module, naga::valid::ModuleInfo),
+    target: CompileTarget,
+) -> Result<Vec<TargetOutput>, Diagnostic> {
+    if target != CompileTarget::Wgsl {
+        tracing::info!("[PSGC] Phase 7: Translating to {}...", target);
+    }
+    crate::translation::translate(module, info, target)
+}
+
+/// Without `cross-compile` the target is always WGSL, which needs no translation
+#[cfg(all(feature = "validation", not(feature = "cross-compile")))]
+fn translate(
+    _validated: &(naga::Module, naga::valid::ModuleInfo),
+    _target: CompileTarget,
+) -> Result<Vec<TargetOutput>, Diagnostic> {
+    Ok(Vec::new())
+}
+
+/// Fail compilation with the given errors and the warnings reported so far
+fn failure(errors: Vec<Diagnostic>, warnings: Vec<Diagnostic>) -> CompileFailure {
+    for error in &errors {
+        tracing::error!("[PSGC] {}", error);
+    }
+    CompileFailure::new(errors.into_iter().chain(warnings).collect())
 }
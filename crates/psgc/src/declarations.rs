@@ -0,0 +1,72 @@
+//! # Graph Declarations
+//!
+//! Names a shader graph declares once at graph level and its nodes refer to.
+//!
+//! graphy's `GraphDescription` has no room for graph-level declarations: it
+//! only carries nodes, connections and descriptive `GraphMetadata`. The
+//! declarations are therefore passed alongside the graph, to
+//! [`compile_with_declarations`](crate::compile_with_declarations), and
+//! serialize next to it.
+
+use serde::{Deserialize, Serialize};
+
+/// Graph-level declarations of a shader graph
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphDeclarations {
+    /// Local variables of the entry functions, read and written by
+    /// `get_variable`/`set_variable` nodes
+    pub variables: Vec<VariableDeclaration>,
+    /// Material parameters, read by `parameter`, `texture_parameter` and
+    /// `sampler_parameter` nodes
+    pub parameters: Vec<ParameterDeclaration>,
+}
+
+impl GraphDeclarations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a local variable of the given WGSL type
+    pub fn with_variable(mut self, name: &str, var_type: &str, initial_value: Option<&str>) -> Self {
+        self.variables.push(VariableDeclaration {
+            name: name.to_string(),
+            var_type: var_type.to_string(),
+            initial_value: initial_value.map(str::to_string),
+        });
+        self
+    }
+
+    /// Declare a material parameter
+    ///
+    /// `param_type` is a uniform type (`float`, `int`, `bool`, `color` or a WGSL
+    /// scalar/vector type), a texture type, or a sampler type.
+    pub fn with_parameter(mut self, name: &str, param_type: &str, default: Option<&str>) -> Self {
+        self.parameters.push(ParameterDeclaration {
+            name: name.to_string(),
+            param_type: param_type.to_string(),
+            default: default.map(str::to_string),
+        });
+        self
+    }
+}
+
+/// A mutable local variable, emitted as a `var` at the top of each entry function
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariableDeclaration {
+    pub name: String,
+    /// WGSL scalar or vector type
+    pub var_type: String,
+    /// Starting value, the zero value of the type when unset
+    pub initial_value: Option<String>,
+}
+
+/// A material parameter, bound in the uniform buffer or as its own resource
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParameterDeclaration {
+    pub name: String,
+    /// Parameter type, see [`GraphDeclarations::with_parameter`]
+    pub param_type: String,
+    /// Value for new material instances; textures and samplers have none
+    pub default: Option<String>,
+}
@@ -0,0 +1,309 @@
+//! # Diagnostics
+//!
+//! Structured errors and warnings reported by the compiler.
+//!
+//! Every diagnostic names the node (and pin, where there is one) responsible,
+//! so editors can highlight the offending part of the graph instead of
+//! showing free-form text. Errors in graph-level declarations, which belong
+//! to no node, name the declaration in their message.
+
+use crate::typecheck::{ConversionMode, ImplicitConversion, TypeError};
+use graphy::GraphDescription;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Compilation failed
+    Error,
+    /// Compilation succeeded, but the graph likely doesn't do what was meant
+    Warning,
+}
+
+/// Machine-readable kind of a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticCode {
+    /// A connection carries a value of the wrong type
+    TypeMismatch,
+    /// A connection needs an implicit conversion the policy denies
+    ConversionDenied,
+    /// An implicit conversion was inserted on a connection
+    ImplicitConversion,
+    /// A connection comes from a node that doesn't produce a value
+    NoOutputValue,
+    /// A swizzle mask doesn't fit its input
+    InvalidSwizzle,
+    /// A local variable is invalid or declared inconsistently
+    InvalidVariable,
+    /// A material parameter is invalid or declared inconsistently
+    InvalidParameter,
+    /// A node's type isn't in the shader node registry
+    UnknownNodeType,
+    /// A constant can't be represented in the type of its pin
+    InvalidConstant,
+    /// A resource input has no connection and no possible default
+    UnconnectedInput,
+    /// A value is read outside the block it's computed in
+    OutOfScope,
+    /// A loop count is out of bounds
+    InvalidLoopCount,
+    /// A compute workgroup size is out of bounds
+    InvalidWorkgroupSize,
+    /// A node is used in a shader stage it doesn't support
+    UnsupportedStage,
+    /// The entry point for a stage is missing, or entry points can't be combined
+    InvalidEntryPoint,
+    /// A required output node is missing or duplicated
+    InvalidOutput,
+    /// A fragment input has no matching vertex output
+    MissingVarying,
+    /// A node's registered definition can't be compiled
+    InvalidNodeDefinition,
+    /// Graph analysis failed before type checking
+    GraphAnalysis,
+    /// A compile option can't be used as given
+    InvalidOption,
+    /// The generated WGSL failed validation
+    InvalidWgsl,
+    /// The generated module can't be translated to the compile target
+    TranslationFailed,
+}
+
+impl DiagnosticCode {
+    /// Stable snake_case name of the code, as serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TypeMismatch => "type_mismatch",
+            Self::ConversionDenied => "conversion_denied",
+            Self::ImplicitConversion => "implicit_conversion",
+            Self::NoOutputValue => "no_output_value",
+            Self::InvalidSwizzle => "invalid_swizzle",
+            Self::InvalidVariable => "invalid_variable",
+            Self::InvalidParameter => "invalid_parameter",
+            Self::UnknownNodeType => "unknown_node_type",
+            Self::InvalidConstant => "invalid_constant",
+            Self::UnconnectedInput => "unconnected_input",
+            Self::OutOfScope => "out_of_scope",
+            Self::InvalidLoopCount => "invalid_loop_count",
+            Self::InvalidWorkgroupSize => "invalid_workgroup_size",
+            Self::UnsupportedStage => "unsupported_stage",
+            Self::InvalidEntryPoint => "invalid_entry_point",
+            Self::InvalidOutput => "invalid_output",
+            Self::MissingVarying => "missing_varying",
+            Self::InvalidNodeDefinition => "invalid_node_definition",
+            Self::GraphAnalysis => "graph_analysis",
+            Self::InvalidOption => "invalid_option",
+            Self::InvalidWgsl => "invalid_wgsl",
+            Self::TranslationFailed => "translation_failed",
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A range of bytes on one line of the generated WGSL
+///
+/// Columns count bytes like naga's source locations, so spans from the
+/// source map and from validation errors can be compared directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceSpan {
+    /// 1-based line number
+    pub line: u32,
+    /// 1-based column, in bytes
+    pub column: u32,
+    /// Length in bytes
+    pub length: u32,
+}
+
+/// An error or warning about part of a shader graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: DiagnosticCode,
+    pub message: String,
+    /// Node the diagnostic is reported on
+    pub node_id: Option<String>,
+    /// ID of the pin on `node_id` the diagnostic is about
+    pub pin_id: Option<String>,
+    /// Other nodes involved, e.g. the source of a mistyped connection
+    pub related_nodes: Vec<String>,
+    /// Location in the generated WGSL, for errors found in the output
+    pub span: Option<SourceSpan>,
+}
+
+impl Diagnostic {
+    /// Create an error without a location
+    pub fn error(code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+            node_id: None,
+            pin_id: None,
+            related_nodes: Vec::new(),
+            span: None,
+        }
+    }
+
+    /// Create a warning without a location
+    pub fn warning(code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, message)
+        }
+    }
+
+    /// Report the diagnostic on a node
+    pub fn with_node(mut self, node_id: impl Into<String>) -> Self {
+        self.node_id = Some(node_id.into());
+        self
+    }
+
+    /// Report the diagnostic on a pin of its node
+    pub fn with_pin(mut self, pin_id: impl Into<String>) -> Self {
+        self.pin_id = Some(pin_id.into());
+        self
+    }
+
+    /// Add a node involved in the diagnostic
+    pub fn with_related_node(mut self, node_id: impl Into<String>) -> Self {
+        self.related_nodes.push(node_id.into());
+        self
+    }
+
+    /// Locate the diagnostic in the generated WGSL
+    pub fn with_span(mut self, span: SourceSpan) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Report the diagnostic on the input pin of a node with the given name
+    ///
+    /// Resolves the pin's ID from the graph, falling back to the name for
+    /// pins the node instance doesn't declare.
+    pub fn with_input(self, graph: &GraphDescription, node_id: &str, pin_name: &str) -> Self {
+        let pin_id = input_pin_id(graph, node_id, pin_name);
+        self.with_node(node_id).with_pin(pin_id)
+    }
+
+    /// Whether this diagnostic fails compilation
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Convert a type error, locating it on the graph
+    pub fn from_type_error(error: &TypeError, graph: &GraphDescription) -> Self {
+        let code = match error {
+            TypeError::Mismatch { .. } => DiagnosticCode::TypeMismatch,
+            TypeError::ConversionDenied { .. } => DiagnosticCode::ConversionDenied,
+            TypeError::NoOutputValue { .. } => DiagnosticCode::NoOutputValue,
+            TypeError::InvalidSwizzle { .. } => DiagnosticCode::InvalidSwizzle,
+            TypeError::InvalidVariable { .. }
+            | TypeError::InvalidVariableDeclaration { .. } => DiagnosticCode::InvalidVariable,
+            TypeError::InvalidParameter { .. }
+            | TypeError::InvalidParameterDeclaration { .. } => DiagnosticCode::InvalidParameter,
+            TypeError::UnknownNodeType { .. } => DiagnosticCode::UnknownNodeType,
+        };
+        let diagnostic = Self::error(code, error.to_string());
+
+        match error {
+            TypeError::Mismatch { source_node, target_node, target_pin, .. }
+            | TypeError::ConversionDenied { source_node, target_node, target_pin, .. }
+            | TypeError::NoOutputValue { source_node, target_node, target_pin, .. } => diagnostic
+                .with_input(graph, target_node, target_pin)
+                .with_related_node(source_node.clone()),
+            _ => match error.node_id() {
+                Some(node_id) => diagnostic.with_node(node_id),
+                None => diagnostic,
+            },
+        }
+    }
+
+    /// Convert an implicit conversion, as a warning if its policy says so
+    ///
+    /// Returns `None` for silently allowed conversions.
+    pub fn from_conversion(conversion: &ImplicitConversion, graph: &GraphDescription) -> Option<Self> {
+        if conversion.mode != ConversionMode::Warn {
+            return None;
+        }
+
+        Some(
+            Self::warning(DiagnosticCode::ImplicitConversion, conversion.to_string())
+                .with_input(graph, &conversion.target_node, &conversion.target_pin)
+                .with_related_node(conversion.source_node.clone()),
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}[{}]: {}", severity, self.code, self.message)?;
+        if let Some(span) = &self.span {
+            write!(f, " (line {}, column {})", span.line, span.column)?;
+        }
+        Ok(())
+    }
+}
+
+/// ID of a node's input pin with the given name, or the name if there is none
+pub(crate) fn input_pin_id(graph: &GraphDescription, node_id: &str, pin_name: &str) -> String {
+    graph
+        .nodes
+        .get(node_id)
+        .and_then(|node| node.inputs.iter().find(|input| input.pin.name == pin_name))
+        .map(|input| input.id.clone())
+        .unwrap_or_else(|| pin_name.to_string())
+}
+
+/// A failed compilation and everything reported along the way
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompileFailure {
+    /// Errors and warnings, errors first
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl CompileFailure {
+    pub(crate) fn new(mut diagnostics: Vec<Diagnostic>) -> Self {
+        diagnostics.sort_by_key(|diagnostic| diagnostic.severity);
+        Self { diagnostics }
+    }
+
+    /// Diagnostics that failed the compilation
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.is_error())
+    }
+
+    /// Warnings reported before compilation failed
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| !diagnostic.is_error())
+    }
+}
+
+impl From<Diagnostic> for CompileFailure {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self::new(vec![diagnostic])
+    }
+}
+
+impl fmt::Display for CompileFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Shader compilation failed with {} error(s)", self.errors().count())?;
+        for diagnostic in &self.diagnostics {
+            write!(f, "\n  - {}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CompileFailure {}
@@ -0,0 +1,91 @@
+//! # Identifiers
+//!
+//! Checks for the names a graph or its options put into the generated WGSL.
+//!
+//! Variable, parameter and entry point names must be plain WGSL identifiers
+//! that aren't keywords or reserved words, and must not clash with the names
+//! the compiler declares itself: the `VertexOutput` struct and its `input`
+//! and `output` values, the material uniform, the builtin and vertex input
+//! parameters, `node_*` helper functions and `value_*` hoisted lets.
+
+use crate::codegen::interface::{COMPUTE_BUILTINS, FRAGMENT_INPUTS, VERTEX_INPUTS};
+use crate::options::EntryPointNames;
+use crate::uniforms::{UNIFORM_STRUCT_NAME, UNIFORM_VARIABLE_NAME};
+
+/// WGSL keywords
+pub const KEYWORDS: &[&str] = &[
+    "alias", "break", "case", "const", "const_assert", "continue", "continuing",
+    "default", "diagnostic", "discard", "else", "enable", "false", "fn", "for",
+    "if", "let", "loop", "override", "requires", "return", "struct", "switch",
+    "true", "var", "while",
+];
+
+/// Words WGSL reserves for future use
+pub const RESERVED_WORDS: &[&str] = &[
+    "NULL", "Self", "abstract", "active", "alignas", "alignof", "as", "asm",
+    "asm_fragment", "async", "attribute", "auto", "await", "become",
+    "binding_array", "cast", "catch", "class", "co_await", "co_return",
+    "co_yield", "coherent", "column_major", "common", "compile",
+    "compile_fragment", "concept", "const_cast", "consteval", "constexpr",
+    "constinit", "crate", "debugger", "decltype", "delete", "demote",
+    "demote_to_helper", "do", "dynamic_cast", "enum", "explicit", "export",
+    "extends", "extern", "external", "fallthrough", "filter", "final", "finally",
+    "friend", "from", "fxgroup", "get", "goto", "groupshared", "highp", "impl",
+    "implements", "import", "inline", "instanceof", "interface", "layout",
+    "lowp", "macro", "macro_rules", "match", "mediump", "meta", "mod", "module",
+    "move", "mut", "mutable", "namespace", "new", "nil", "noexcept", "noinline",
+    "nointerpolation", "noperspective", "null", "nullptr", "of", "operator",
+    "package", "packoffset", "partition", "pass", "patch", "pixelfragment",
+    "precise", "precision", "premerge", "priv", "protected", "pub", "public",
+    "readonly", "ref", "regardless", "register", "reinterpret_cast", "require",
+    "resource", "restrict", "self", "set", "shared", "sizeof", "smooth", "snorm",
+    "static", "static_assert", "static_cast", "std", "subroutine", "super",
+    "target", "template", "this", "thread_local", "throw", "trait", "try", "type",
+    "typedef", "typeid", "typename", "typeof", "union", "unless", "unorm",
+    "unsafe", "unsized", "use", "using", "varying", "virtual", "volatile", "wgsl",
+    "where", "with", "writeonly", "yield",
+];
+
+/// Names the generated code declares besides the interface parameters
+const GENERATED_NAMES: &[&str] = &[
+    "VertexOutput", "input", "output", "vertex_index",
+    UNIFORM_STRUCT_NAME, UNIFORM_VARIABLE_NAME,
+];
+
+/// Prefixes of generated helper functions and hoisted lets
+const GENERATED_PREFIXES: &[&str] = &["node_", "value_"];
+
+/// Whether a name is a WGSL identifier that isn't a keyword or reserved word
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_"
+        && !name.starts_with("__")
+        && !KEYWORDS.contains(&name)
+        && !RESERVED_WORDS.contains(&name)
+}
+
+/// Whether the generated code declares `name` itself, apart from entry points
+pub fn is_generated_name(name: &str) -> bool {
+    GENERATED_NAMES.contains(&name)
+        || GENERATED_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+        || VERTEX_INPUTS.iter().any(|input| input.node_type == name)
+        || FRAGMENT_INPUTS.iter().any(|input| input.node_type == name)
+        || COMPUTE_BUILTINS.iter().any(|builtin| builtin.node_type == name)
+}
+
+/// Why `name` can't name a variable or parameter of the graph, if it can't
+pub fn check_name(name: &str, entry_points: &EntryPointNames) -> Result<(), String> {
+    let is_entry_point = [&entry_points.vertex, &entry_points.fragment, &entry_points.compute]
+        .iter()
+        .any(|entry_point| entry_point.as_str() == name);
+
+    if !is_identifier(name) {
+        Err(format!("'{}' is not a WGSL identifier, or is a keyword or reserved word", name))
+    } else if is_generated_name(name) || is_entry_point {
+        Err(format!("'{}' is already used by the generated code", name))
+    } else {
+        Ok(())
+    }
+}
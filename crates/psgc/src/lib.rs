@@ -8,7 +8,7 @@
 //! - Shader node metadata system
 //! - WGSL code generation
 //! - Texture sampling and uniform handling
-//! - Vertex and fragment shader support
+//! - Vertex, fragment and compute shader support
 //!
 //! ## Quick Start
 //!
@@ -20,10 +20,15 @@
 //! // ... build graph with shader nodes
 //!
 //! match compile_shader(&graph) {
-//!     Ok(wgsl_code) => {
-//!         std::fs::write("shader.wgsl", wgsl_code)?;
+//!     Ok(compiled) => {
+//!         std::fs::write("shader.wgsl", &compiled.wgsl)?;
+//!         std::fs::write("shader.json", serde_json::to_string_pretty(&compiled.reflection)?)?;
+//!     }
+//!     Err(failure) => {
+//!         for diagnostic in &failure.diagnostics {
+//!             eprintln!("{} (node {:?})", diagnostic, diagnostic.node_id);
+//!         }
 //!     }
-//!     Err(e) => eprintln!("Compilation failed: {}", e),
 //! }
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
@@ -34,20 +39,53 @@
 //!
 //! 1. **Metadata Loading** - Load shader node definitions
 //! 2. **Data Flow Analysis** - Build dependency graph (via Graphy)
-//! 3. **Execution Flow Analysis** - Map shader stages (via Graphy)
-//! 4. **Code Generation** - Generate WGSL shader code
+//! 3. **Type Checking** - Verify connected pins have matching types
+//! 4. **Execution Flow Analysis** - Map shader stages (via Graphy)
+//! 5. **Code Generation** - Generate WGSL shader code
+//! 6. **Validation** - Optionally check the output with naga (`validation` feature)
+//! 7. **Translation** - Optionally lower to SPIR-V, GLSL, HLSL or MSL (`cross-compile` feature)
 
 pub mod metadata;
+pub mod types;
+pub mod typecheck;
+pub mod diagnostics;
+pub mod options;
+pub mod declarations;
+pub mod identifiers;
+pub mod variables;
+pub mod uniforms;
+pub mod reflection;
+pub mod source_map;
 pub mod codegen;
 pub mod compiler;
+pub mod targets;
+#[cfg(feature = "validation")]
+mod validation;
+#[cfg(feature = "cross-compile")]
+mod translation;
 
 // Re-export the main compilation API
 pub use compiler::{
     compile_shader,
     compile_vertex_shader,
     compile_fragment_shader,
+    compile_compute_shader,
+    compile_module,
+    compile_shader_with_policy,
+    compile_with_options,
+    compile_with_declarations,
+};
+pub use codegen::{GeneratedCode, ShaderStage};
+pub use diagnostics::{CompileFailure, Diagnostic, DiagnosticCode, Severity, SourceSpan};
+pub use options::{CompileOptions, EntryPointNames, OptimizationLevel};
+pub use declarations::{GraphDeclarations, ParameterDeclaration, VariableDeclaration};
+pub use targets::{CompileTarget, TargetBinding, TargetCode, TargetOutput};
+pub use source_map::{SourceMap, SourceMapEntry, SpanKind};
+pub use reflection::{
+    CompiledShader, ShaderReflection, EntryPointReflection, BindingReflection,
+    ResourceReflection, UniformBufferReflection, UniformFieldReflection,
+    TextureDimension, TextureSampleType, SamplerBindingType, VertexInputReflection,
 };
-pub use codegen::ShaderStage;
 
 // Re-export Graphy types for convenience
 pub use graphy::{
@@ -64,3 +102,11 @@ pub use metadata::{
     ShaderMetadataProvider,
     get_shader_nodes,
 };
+pub use wgsl_std::ShaderNodeInfo;
+pub use types::{ScalarType, WgslType};
+pub use variables::LocalVariable;
+pub use uniforms::{MaterialLayout, ResourceBinding, UniformField, UniformLayout};
+pub use typecheck::{
+    TypeChecker, TypeError, TypeCheckReport,
+    ConversionKind, ConversionMode, ConversionPolicy, ImplicitConversion,
+};
@@ -2,21 +2,24 @@
 //!
 //! Integrates wgsl_std shader nodes into PSGC's metadata system
 
-use graphy::NodeMetadataProvider;
+use graphy::{NodeInstance, NodeMetadataProvider, PropertyValue};
 use graphy::core::NodeMetadata;
-use std::collections::HashMap;
+use wgsl_std::{NodeOutput, ShaderNodeInfo};
+use std::collections::BTreeMap;
 
 /// PSGC shader metadata provider
 /// 
-/// Loads shader nodes from wgsl_std registry and provides them to the compiler
+/// Loads shader nodes from wgsl_std registry and provides them to the compiler.
+/// Nodes are kept sorted by name, so listing them always gives the same order.
 pub struct ShaderMetadataProvider {
-    nodes: HashMap<String, NodeMetadata>,
+    nodes: BTreeMap<String, NodeMetadata>,
+    node_info: BTreeMap<String, ShaderNodeInfo>,
 }
 
 impl ShaderMetadataProvider {
     /// Create a new provider by loading all nodes from wgsl_std
     pub fn new() -> Self {
-        let mut nodes = HashMap::new();
+        let mut nodes = BTreeMap::new();
 
         // Load all shader nodes from wgsl_std registry
         for node_fn in wgsl_std::SHADER_REGISTRY.iter() {
@@ -27,7 +30,47 @@ impl ShaderMetadataProvider {
 
         tracing::info!("[PSGC] Loaded {} shader nodes from wgsl_std", nodes.len());
 
-        Self { nodes }
+        // Load shader-specific node info
+        let mut node_info = BTreeMap::new();
+        for info_fn in wgsl_std::SHADER_NODE_INFO.iter() {
+            let info = info_fn();
+            if !nodes.contains_key(&info.name) {
+                tracing::warn!("[PSGC] Shader node info for unknown node: {}", info.name);
+            }
+            node_info.insert(info.name.clone(), info);
+        }
+
+        Self { nodes, node_info }
+    }
+
+    /// Get the shader-specific info for a node type, if any was registered
+    pub fn get_node_info(&self, node_type: &str) -> Option<&ShaderNodeInfo> {
+        self.node_info.get(node_type)
+    }
+
+    /// Get the named output a connection reads from, if the node declares one
+    ///
+    /// `source_pin` may be either the output pin's ID or its name.
+    pub fn get_node_output(&self, node: &NodeInstance, source_pin: &str) -> Option<&NodeOutput> {
+        let info = self.get_node_info(&node.node_type)?;
+        let pin_name = node.outputs
+            .iter()
+            .find(|output| output.id == source_pin)
+            .map(|output| output.pin.name.as_str())
+            .unwrap_or(source_pin);
+        info.output(pin_name)
+    }
+
+    /// Get the swizzle mask of a node whose type swizzles its value
+    ///
+    /// Returns `None` for node types without a swizzle property, and an empty
+    /// mask when the property isn't set to a string.
+    pub fn get_swizzle_mask(&self, node: &NodeInstance) -> Option<String> {
+        let property = self.get_node_info(&node.node_type)?.swizzle_property.as_ref()?;
+        match node.properties.get(property) {
+            Some(PropertyValue::String(mask)) => Some(mask.trim().to_string()),
+            _ => Some(String::new()),
+        }
     }
 }
 
@@ -0,0 +1,168 @@
+//! # Compile Options
+//!
+//! Configuration of a compilation run, passed to
+//! [`compile_with_options`](crate::compile_with_options).
+
+use crate::codegen::ShaderStage;
+use crate::diagnostics::{Diagnostic, DiagnosticCode};
+use crate::identifiers;
+use crate::targets::CompileTarget;
+use crate::typecheck::ConversionPolicy;
+use crate::uniforms;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// How much work code generation spends on the size of the output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OptimizationLevel {
+    /// Emit everything the graph declares, as written
+    None,
+    /// Skip helper functions of nodes the entry points never reach, and bind
+    /// pure nodes read more than once to a `let` at their first use instead
+    /// of inlining them at every read
+    #[default]
+    Basic,
+    /// Also bind pure nodes expensive to evaluate to a `let`, even when they
+    /// are read only once
+    Full,
+}
+
+/// Names of the generated entry point functions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryPointNames {
+    pub vertex: String,
+    pub fragment: String,
+    pub compute: String,
+}
+
+impl EntryPointNames {
+    /// Function name of the given stage's entry point
+    pub fn name(&self, stage: ShaderStage) -> &str {
+        match stage {
+            ShaderStage::Vertex => &self.vertex,
+            ShaderStage::Fragment => &self.fragment,
+            ShaderStage::Compute => &self.compute,
+        }
+    }
+}
+
+impl Default for EntryPointNames {
+    /// The names of the entry nodes: `vertex_main`, `fragment_main` and `compute_main`
+    fn default() -> Self {
+        Self {
+            vertex: ShaderStage::Vertex.entry_point().to_string(),
+            fragment: ShaderStage::Fragment.entry_point().to_string(),
+            compute: ShaderStage::Compute.entry_point().to_string(),
+        }
+    }
+}
+
+/// Configuration of a compilation run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompileOptions {
+    /// Stages to generate entry points for
+    ///
+    /// Empty detects them from the graph's entry nodes. A single stage, or
+    /// vertex and fragment together (as one module), can be requested.
+    pub stages: Vec<ShaderStage>,
+    /// Names of the generated entry point functions
+    pub entry_points: EntryPointNames,
+    /// Bind group of the material uniform buffer and resources
+    pub material_group: u32,
+    pub optimization: OptimizationLevel,
+    /// Emit the "auto-generated" header comment
+    pub header: bool,
+    /// Emit comments describing the generated code, e.g. uniform field offsets
+    pub comments: bool,
+    /// Precede each generated statement with the ID and type of its node
+    pub debug_annotations: bool,
+    /// WGSL extensions declared with `enable` directives, e.g. `f16`
+    pub features: BTreeSet<String>,
+    /// Policy for implicit conversions on mistyped connections
+    pub conversion_policy: ConversionPolicy,
+    /// Parse and validate the generated WGSL with naga before returning it
+    ///
+    /// Requires the `validation` feature.
+    pub validate: bool,
+    /// Language to translate the generated WGSL to
+    ///
+    /// Targets other than WGSL require the `cross-compile` feature, and
+    /// always validate the WGSL first.
+    pub target: CompileTarget,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            stages: Vec::new(),
+            entry_points: EntryPointNames::default(),
+            material_group: uniforms::MATERIAL_GROUP,
+            optimization: OptimizationLevel::default(),
+            header: true,
+            comments: true,
+            debug_annotations: false,
+            features: BTreeSet::new(),
+            conversion_policy: ConversionPolicy::default(),
+            validate: false,
+            target: CompileTarget::default(),
+        }
+    }
+}
+
+impl CompileOptions {
+    /// Options generating the given stages, with everything else at its default
+    pub fn for_stages(stages: &[ShaderStage]) -> Self {
+        Self {
+            stages: stages.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    /// Check that names and features can be emitted as written
+    pub(crate) fn validate(&self) -> Vec<Diagnostic> {
+        let mut errors = Vec::new();
+
+        let stages = [ShaderStage::Vertex, ShaderStage::Fragment, ShaderStage::Compute];
+        for (index, stage) in stages.iter().enumerate() {
+            let name = self.entry_points.name(*stage);
+            if !identifiers::is_identifier(name) || identifiers::is_generated_name(name) {
+                errors.push(Diagnostic::error(
+                    DiagnosticCode::InvalidOption,
+                    format!("'{}' is not a valid {} entry point name", name, stage.name()),
+                ));
+            } else if stages[..index].iter().any(|other| self.entry_points.name(*other) == name) {
+                errors.push(Diagnostic::error(
+                    DiagnosticCode::InvalidOption,
+                    format!("Entry point name '{}' is used for more than one stage", name),
+                ));
+            }
+        }
+
+        if self.validate && !cfg!(feature = "validation") {
+            errors.push(Diagnostic::error(
+                DiagnosticCode::InvalidOption,
+                "Validating generated WGSL requires the `validation` feature of psgc",
+            ));
+        }
+
+        if self.target != CompileTarget::Wgsl && !cfg!(feature = "cross-compile") {
+            errors.push(Diagnostic::error(
+                DiagnosticCode::InvalidOption,
+                format!("Compiling to {} requires the `cross-compile` feature of psgc", self.target),
+            ));
+        }
+
+        for feature in &self.features {
+            if !identifiers::is_identifier(feature) {
+                errors.push(Diagnostic::error(
+                    DiagnosticCode::InvalidOption,
+                    format!("'{}' is not a valid WGSL extension name", feature),
+                ));
+            }
+        }
+
+        errors
+    }
+}
@@ -0,0 +1,410 @@
+//! # Shader Reflection
+//!
+//! Description of a compiled shader's interface, returned alongside the WGSL
+//! so renderers can build bind group and vertex buffer layouts without
+//! parsing the generated code.
+
+use crate::codegen::{ShaderStage, VertexInput};
+use crate::diagnostics::Diagnostic;
+use crate::source_map::SourceMap;
+use crate::targets::TargetOutput;
+use crate::types::{ScalarType, WgslType};
+use crate::uniforms::{self, MaterialLayout};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A compiled shader graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompiledShader {
+    /// Generated WGSL source code
+    pub wgsl: String,
+    /// Interface of the generated code
+    pub reflection: ShaderReflection,
+    /// Graph nodes and pins each span of `wgsl` was generated from
+    pub source_map: SourceMap,
+    /// Warnings reported while compiling
+    pub diagnostics: Vec<Diagnostic>,
+    /// Code translated for `CompileOptions::target`, empty for WGSL
+    ///
+    /// GLSL has one output per entry point, other targets a single output.
+    pub targets: Vec<TargetOutput>,
+}
+
+/// Interface of a compiled shader
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShaderReflection {
+    /// Entry points in the module, in generation order
+    pub entry_points: Vec<EntryPointReflection>,
+    /// Every resource binding, in group and binding order
+    pub bindings: Vec<BindingReflection>,
+    /// Vertex attributes read by the vertex entry point, in location order
+    pub vertex_inputs: Vec<VertexInputReflection>,
+}
+
+impl ShaderReflection {
+    /// Entry point of the given stage, if the module has one
+    pub fn entry_point(&self, stage: ShaderStage) -> Option<&EntryPointReflection> {
+        self.entry_points.iter().find(|entry_point| entry_point.stage == stage)
+    }
+
+    /// Binding at the given group and binding index
+    pub fn binding(&self, group: u32, binding: u32) -> Option<&BindingReflection> {
+        self.bindings
+            .iter()
+            .find(|reflection| reflection.group == group && reflection.binding == binding)
+    }
+
+    /// Layout of the material uniform buffer, if the graph has value parameters
+    pub fn uniform_buffer(&self) -> Option<&UniformBufferReflection> {
+        self.bindings.iter().find_map(|binding| match &binding.resource {
+            ResourceReflection::UniformBuffer(buffer) => Some(buffer),
+            _ => None,
+        })
+    }
+}
+
+/// A generated entry point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryPointReflection {
+    /// WGSL function name
+    pub name: String,
+    pub stage: ShaderStage,
+    /// `@workgroup_size`, for compute entry points
+    pub workgroup_size: Option<[u32; 3]>,
+}
+
+/// A `@group`/`@binding` global
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BindingReflection {
+    pub group: u32,
+    pub binding: u32,
+    /// WGSL variable name
+    pub name: String,
+    /// Stages of the module whose entry functions read the binding, empty if
+    /// none does
+    pub stages: Vec<ShaderStage>,
+    pub resource: ResourceReflection,
+}
+
+/// What a binding holds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ResourceReflection {
+    UniformBuffer(UniformBufferReflection),
+    Texture {
+        /// WGSL type as declared, e.g. `texture_2d<f32>`
+        wgsl_type: String,
+        dimension: TextureDimension,
+        sample_type: TextureSampleType,
+    },
+    Sampler {
+        binding_type: SamplerBindingType,
+    },
+}
+
+/// A uniform buffer and the layout of its struct
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniformBufferReflection {
+    /// WGSL struct name
+    pub struct_name: String,
+    /// Size of the struct in bytes
+    pub size: u32,
+    pub fields: Vec<UniformFieldReflection>,
+}
+
+impl UniformBufferReflection {
+    /// Generate a Rust struct matching the uniform struct byte for byte
+    ///
+    /// The struct is `#[repr(C)]` with explicit padding fields and derives
+    /// `bytemuck::Pod`/`Zeroable`, so it can be uploaded with
+    /// `bytemuck::bytes_of`. A compile-time assertion checks its size.
+    ///
+    /// Fields named after Rust keywords are escaped as raw identifiers, or
+    /// get a `_` suffix for the keywords that can't be raw. Fails if `name`
+    /// isn't a Rust identifier.
+    pub fn generate_rust_struct(&self, name: &str) -> Result<String, String> {
+        if !is_rust_identifier(name) {
+            return Err(format!("'{}' is not a valid Rust struct name", name));
+        }
+
+        let mut code = format!("/// Rust mirror of the WGSL `{}` uniform struct\n", self.struct_name);
+        code.push_str("#[repr(C)]\n");
+        code.push_str("#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]\n");
+        code.push_str(&format!("pub struct {} {{\n", name));
+
+        let mut used_names: BTreeSet<String> = self.fields.iter().map(|field| field.name.clone()).collect();
+        let mut offset = 0;
+        let mut padding = 0;
+        // Padding fields skip the names of parameters called `_padN`
+        let mut pad_to = |code: &mut String, used_names: &mut BTreeSet<String>, offset: u32, target: u32| {
+            if target > offset {
+                let mut pad_name = format!("_pad{}", padding);
+                while used_names.contains(&pad_name) {
+                    padding += 1;
+                    pad_name = format!("_pad{}", padding);
+                }
+                code.push_str(&format!("    pub {}: [u32; {}],\n", pad_name, (target - offset) / 4));
+                used_names.insert(pad_name);
+                padding += 1;
+            }
+        };
+
+        for field in &self.fields {
+            pad_to(&mut code, &mut used_names, offset, field.offset);
+            code.push_str(&format!(
+                "    pub {}: {}, // offset {}\n",
+                rust_identifier(&field.name, &mut used_names),
+                rust_type(&WgslType::parse(&field.wgsl_type)),
+                field.offset
+            ));
+            offset = field.offset + field.size;
+        }
+        pad_to(&mut code, &mut used_names, offset, self.size);
+
+        code.push_str("}\n\n");
+        code.push_str(&format!(
+            "const _: () = assert!(std::mem::size_of::<{}>() == {});\n",
+            name, self.size
+        ));
+        Ok(code)
+    }
+}
+
+/// Rust type with the same size and layout as a host-shareable WGSL scalar or vector
+fn rust_type(wgsl_type: &WgslType) -> String {
+    let scalar = match wgsl_type.scalar_type() {
+        Some(ScalarType::I32) => "i32",
+        Some(ScalarType::U32 | ScalarType::Bool) => "u32",
+        _ => "f32",
+    };
+    match wgsl_type {
+        WgslType::Vector(size, _) => format!("[{}; {}]", scalar, size),
+        _ => scalar.to_string(),
+    }
+}
+
+/// Rust keywords, strict and reserved
+const RUST_KEYWORDS: &[&str] = &[
+    "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+    "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv",
+    "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Keywords that can't be used as raw identifiers
+const NON_RAW_KEYWORDS: &[&str] = &["Self", "crate", "self", "super"];
+
+/// Whether a name is a Rust identifier that isn't a keyword
+fn is_rust_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_"
+        && !RUST_KEYWORDS.contains(&name)
+}
+
+/// Field name usable in Rust
+///
+/// Keywords are escaped as raw identifiers, except those that can't be raw,
+/// which get `_` appended until the name is unused by the other fields.
+fn rust_identifier(name: &str, used_names: &mut BTreeSet<String>) -> String {
+    if NON_RAW_KEYWORDS.contains(&name) {
+        let mut renamed = format!("{}_", name);
+        while used_names.contains(&renamed) {
+            renamed.push('_');
+        }
+        used_names.insert(renamed.clone());
+        renamed
+    } else if RUST_KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// A field of a uniform struct
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniformFieldReflection {
+    pub name: String,
+    /// WGSL type stored in the buffer
+    pub wgsl_type: String,
+    pub offset: u32,
+    pub size: u32,
+    pub align: u32,
+    /// Default value as declared
+    pub default: Option<String>,
+}
+
+/// View dimension of a texture binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextureDimension {
+    D1,
+    D2,
+    D2Array,
+    Cube,
+    CubeArray,
+    D3,
+}
+
+/// Sample type of a texture binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextureSampleType {
+    Float,
+    Sint,
+    Uint,
+    Depth,
+}
+
+/// Binding type of a sampler, as in WebGPU's `GPUSamplerBindingType`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SamplerBindingType {
+    /// A `sampler` that may filter linearly
+    #[default]
+    Filtering,
+    /// A `sampler` limited to nearest filtering, e.g. for unfilterable textures
+    NonFiltering,
+    /// A `sampler_comparison` for depth comparisons
+    Comparison,
+}
+
+/// A vertex attribute read by the vertex entry point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VertexInputReflection {
+    /// WGSL parameter name
+    pub name: String,
+    pub location: u32,
+    /// WGSL type of the attribute
+    pub wgsl_type: String,
+    /// Vertex format name as used by WebGPU, e.g. `float32x3`
+    pub format: String,
+    /// Size of the attribute in bytes
+    pub size: u32,
+}
+
+impl VertexInputReflection {
+    pub(crate) fn new(input: &VertexInput) -> Self {
+        let wgsl_type = WgslType::parse(input.wgsl_type);
+        let components = wgsl_type.component_count().unwrap_or(1) as u32;
+        let scalar = match wgsl_type.scalar_type() {
+            Some(ScalarType::I32) => "sint32",
+            Some(ScalarType::U32) => "uint32",
+            _ => "float32",
+        };
+        let format = if components == 1 {
+            scalar.to_string()
+        } else {
+            format!("{}x{}", scalar, components)
+        };
+
+        Self {
+            name: input.node_type.to_string(),
+            location: input.location,
+            wgsl_type: input.wgsl_type.to_string(),
+            format,
+            size: components * 4,
+        }
+    }
+}
+
+/// Describe the material uniform buffer and resources as bindings
+///
+/// `parameter_stages` lists the stages reading each parameter. A resource is
+/// visible to the stages reading its parameter, the uniform buffer to the
+/// stages reading any of its fields.
+pub(crate) fn material_bindings(
+    material: &MaterialLayout,
+    stages: &[ShaderStage],
+    parameter_stages: &BTreeMap<&str, Vec<ShaderStage>>,
+) -> Vec<BindingReflection> {
+    let mut bindings = Vec::new();
+    let read_by = |names: &[&str]| -> Vec<ShaderStage> {
+        stages
+            .iter()
+            .copied()
+            .filter(|stage| {
+                names
+                    .iter()
+                    .any(|name| parameter_stages.get(name).is_some_and(|read_by| read_by.contains(stage)))
+            })
+            .collect()
+    };
+
+    if !material.uniforms.is_empty() {
+        let fields: Vec<&str> = material.uniforms.fields.iter().map(|field| field.name.as_str()).collect();
+        bindings.push(BindingReflection {
+            group: material.group,
+            binding: uniforms::MATERIAL_BINDING,
+            name: uniforms::UNIFORM_VARIABLE_NAME.to_string(),
+            stages: read_by(&fields),
+            resource: ResourceReflection::UniformBuffer(UniformBufferReflection {
+                struct_name: uniforms::UNIFORM_STRUCT_NAME.to_string(),
+                size: material.uniforms.size,
+                fields: material
+                    .uniforms
+                    .fields
+                    .iter()
+                    .map(|field| UniformFieldReflection {
+                        name: field.name.clone(),
+                        wgsl_type: field.storage_type.to_string(),
+                        offset: field.offset,
+                        size: field.size,
+                        align: field.align,
+                        default: field.default.clone(),
+                    })
+                    .collect(),
+            }),
+        });
+    }
+
+    for resource in &material.resources {
+        let reflection = match &resource.resource_type {
+            WgslType::Texture(wgsl_type) => texture_reflection(wgsl_type),
+            _ => ResourceReflection::Sampler {
+                binding_type: resource.sampler_binding_type.unwrap_or_default(),
+            },
+        };
+        bindings.push(BindingReflection {
+            group: resource.group,
+            binding: resource.binding,
+            name: resource.name.clone(),
+            stages: read_by(&[resource.name.as_str()]),
+            resource: reflection,
+        });
+    }
+
+    bindings.sort_by_key(|binding| (binding.group, binding.binding));
+    bindings
+}
+
+/// Describe a `texture_*` type
+fn texture_reflection(wgsl_type: &str) -> ResourceReflection {
+    let (base, sampled) = match wgsl_type.split_once('<') {
+        Some((base, rest)) => (base, rest.trim_end_matches('>')),
+        None => (wgsl_type, ""),
+    };
+
+    let dimension = match base.trim_start_matches("texture_").trim_start_matches("depth_") {
+        "1d" => TextureDimension::D1,
+        "2d_array" => TextureDimension::D2Array,
+        "cube" => TextureDimension::Cube,
+        "cube_array" => TextureDimension::CubeArray,
+        "3d" => TextureDimension::D3,
+        _ => TextureDimension::D2,
+    };
+
+    let sample_type = if base.starts_with("texture_depth_") {
+        TextureSampleType::Depth
+    } else {
+        match sampled {
+            "i32" => TextureSampleType::Sint,
+            "u32" => TextureSampleType::Uint,
+            _ => TextureSampleType::Float,
+        }
+    };
+
+    ResourceReflection::Texture {
+        wgsl_type: wgsl_type.to_string(),
+        dimension,
+        sample_type,
+    }
+}
@@ -0,0 +1,67 @@
+//! # Source Maps
+//!
+//! Mapping from spans of the generated WGSL back to the graph nodes and
+//! pins they were generated from, returned with the compiled shader so
+//! editors can jump from an error line to its node, or highlight the code
+//! a selected node produced.
+
+use crate::diagnostics::SourceSpan;
+use serde::{Deserialize, Serialize};
+
+/// What a source map entry covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanKind {
+    /// A whole line generated by a node: a statement, or part of the
+    /// block or function the node opened
+    Statement,
+    /// An expression: the value read from an output pin, or a constant or
+    /// default on an input pin
+    Expression,
+}
+
+/// A span of generated WGSL and where in the graph it comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceMapEntry {
+    pub span: SourceSpan,
+    pub kind: SpanKind,
+    pub node_id: String,
+    /// Pin of `node_id` the expression belongs to, `None` for statements
+    pub pin_id: Option<String>,
+}
+
+/// Spans of generated WGSL and the graph nodes they come from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceMap {
+    /// Entries in line and column order, outer spans before the spans they contain
+    pub entries: Vec<SourceMapEntry>,
+}
+
+impl SourceMap {
+    /// Entries covering a position (1-based line and byte column), innermost first
+    pub fn entries_at(&self, line: u32, column: u32) -> Vec<&SourceMapEntry> {
+        let mut entries: Vec<_> = self.entries
+            .iter()
+            .filter(|entry| {
+                entry.span.line == line
+                    && entry.span.column <= column
+                    && column < entry.span.column + entry.span.length
+            })
+            .collect();
+        entries.reverse();
+        entries
+    }
+
+    /// ID of the node whose statement is on a line (1-based), if any
+    pub fn node_at_line(&self, line: u32) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| entry.span.line == line && entry.kind == SpanKind::Statement)
+            .map(|entry| entry.node_id.as_str())
+    }
+
+    /// Every span generated for a node, statements and expressions
+    pub fn spans_of<'s>(&'s self, node_id: &'s str) -> impl Iterator<Item = &'s SourceMapEntry> + 's {
+        self.entries.iter().filter(move |entry| entry.node_id == node_id)
+    }
+}
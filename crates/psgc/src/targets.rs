@@ -0,0 +1,112 @@
+//! # Compile Targets
+//!
+//! Shading languages a graph can be compiled to besides WGSL.
+//!
+//! WGSL is always generated; other targets are translated from it with
+//! naga's backends, which requires the `cross-compile` feature.
+
+use crate::codegen::ShaderStage;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Language of the compiled shader
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompileTarget {
+    /// WGSL only
+    #[default]
+    Wgsl,
+    /// SPIR-V binary for Vulkan, one module with every entry point
+    SpirV,
+    /// GLSL source, one per entry point
+    ///
+    /// `version` 100, 300, 310 and 320 select GLSL ES, anything else desktop
+    /// GLSL (e.g. 330 or 450).
+    Glsl { version: u16 },
+    /// HLSL source with every entry point
+    ///
+    /// `shader_model` is written without the dot, e.g. 50 for SM 5.0 or 60
+    /// for SM 6.0 (50, 51 and 60 through 67 are supported).
+    Hlsl { shader_model: u8 },
+    /// Metal Shading Language source with every entry point
+    Msl,
+}
+
+impl CompileTarget {
+    /// Whether this GLSL version is GLSL ES
+    pub(crate) fn is_glsl_es(version: u16) -> bool {
+        matches!(version, 100 | 300 | 310 | 320)
+    }
+}
+
+impl fmt::Display for CompileTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wgsl => write!(f, "WGSL"),
+            Self::SpirV => write!(f, "SPIR-V"),
+            Self::Glsl { version } if Self::is_glsl_es(*version) => write!(f, "GLSL ES {}", version),
+            Self::Glsl { version } => write!(f, "GLSL {}", version),
+            Self::Hlsl { shader_model } => {
+                write!(f, "HLSL SM {}.{}", shader_model / 10, shader_model % 10)
+            }
+            Self::Msl => write!(f, "MSL"),
+        }
+    }
+}
+
+/// Code translated for a target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetCode {
+    /// Source code of a text language
+    Text(String),
+    /// SPIR-V words
+    SpirV(Vec<u32>),
+}
+
+impl TargetCode {
+    /// Source code, for text targets
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::Text(code) => Some(code),
+            Self::SpirV(_) => None,
+        }
+    }
+
+    /// SPIR-V words, for the SPIR-V target
+    pub fn as_spirv(&self) -> Option<&[u32]> {
+        match self {
+            Self::SpirV(words) => Some(words),
+            Self::Text(_) => None,
+        }
+    }
+}
+
+/// A translated shader, or one stage of it for targets with one entry point per output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetOutput {
+    pub target: CompileTarget,
+    /// Stages of the entry points in `code`
+    pub stages: Vec<ShaderStage>,
+    /// Name of each entry point in `code`, in `stages` order
+    ///
+    /// Backends rename entry points that clash with the target's keywords,
+    /// and GLSL always names its entry point `main`.
+    pub entry_points: Vec<String>,
+    pub code: TargetCode,
+    /// Names the code gives the reflected bindings
+    ///
+    /// Only filled for GLSL, which addresses uniform blocks and combined
+    /// texture-samplers by name. The other targets keep the reflected
+    /// numbers: SPIR-V as descriptor set and binding, HLSL as space and
+    /// register, MSL as resource index.
+    pub bindings: Vec<TargetBinding>,
+}
+
+/// Name of a reflected binding in target code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetBinding {
+    pub group: u32,
+    pub binding: u32,
+    pub name: String,
+}
@@ -0,0 +1,196 @@
+//! # Target Translation
+//!
+//! Lowers the validated WGSL module to other shading languages with naga's
+//! backends.
+//!
+//! Only available with the `cross-compile` feature.
+
+use crate::codegen::ShaderStage;
+use crate::diagnostics::{Diagnostic, DiagnosticCode};
+use crate::targets::{CompileTarget, TargetBinding, TargetCode, TargetOutput};
+use crate::validation;
+use naga::back::{glsl, hlsl, msl, spv};
+use naga::valid::ModuleInfo;
+use naga::Module;
+
+/// Translate a validated module to the given target
+///
+/// Returns no outputs for WGSL, which needs no translation.
+pub(crate) fn translate(
+    module: &Module,
+    info: &ModuleInfo,
+    target: CompileTarget,
+) -> Result<Vec<TargetOutput>, Diagnostic> {
+    match target {
+        CompileTarget::Wgsl => Ok(Vec::new()),
+        CompileTarget::SpirV => translate_spirv(module, info),
+        CompileTarget::Glsl { version } => translate_glsl(module, info, version),
+        CompileTarget::Hlsl { shader_model } => translate_hlsl(module, info, shader_model),
+        CompileTarget::Msl => translate_msl(module, info),
+    }
+}
+
+fn translate_spirv(module: &Module, info: &ModuleInfo) -> Result<Vec<TargetOutput>, Diagnostic> {
+    let words = spv::write_vec(module, info, &spv::Options::default(), None)
+        .map_err(|error| failed(CompileTarget::SpirV, &error))?;
+
+    Ok(vec![TargetOutput {
+        target: CompileTarget::SpirV,
+        stages: module_stages(module),
+        entry_points: module.entry_points.iter().map(|entry_point| entry_point.name.clone()).collect(),
+        code: TargetCode::SpirV(words),
+        bindings: Vec::new(),
+    }])
+}
+
+/// One GLSL output per entry point, as GLSL has a single `main` per shader
+fn translate_glsl(module: &Module, info: &ModuleInfo, version: u16) -> Result<Vec<TargetOutput>, Diagnostic> {
+    let target = CompileTarget::Glsl { version };
+    let options = glsl::Options {
+        version: if CompileTarget::is_glsl_es(version) {
+            glsl::Version::Embedded { version, is_webgl: false }
+        } else {
+            glsl::Version::Desktop(version)
+        },
+        ..glsl::Options::default()
+    };
+
+    let mut outputs = Vec::new();
+    for entry_point in &module.entry_points {
+        let pipeline_options = glsl::PipelineOptions {
+            shader_stage: entry_point.stage,
+            entry_point: entry_point.name.clone(),
+            multiview: None,
+        };
+
+        let mut code = String::new();
+        let reflection = glsl::Writer::new(
+            &mut code,
+            module,
+            info,
+            &options,
+            &pipeline_options,
+            naga::proc::BoundsCheckPolicies::default(),
+        )
+        .and_then(|mut writer| writer.write())
+        .map_err(|error| failed(target, &error))?;
+
+        let binding = |handle: naga::Handle<naga::GlobalVariable>| module.global_variables[handle].binding.clone();
+        let mut bindings: Vec<TargetBinding> = reflection
+            .uniforms
+            .iter()
+            .filter_map(|(handle, name)| binding(*handle).map(|binding| (binding, name.clone())))
+            .chain(reflection.texture_mapping.iter().flat_map(|(name, mapping)| {
+                std::iter::once(mapping.texture)
+                    .chain(mapping.sampler)
+                    .filter_map(|handle| binding(handle).map(|binding| (binding, name.clone())))
+            }))
+            .map(|(binding, name)| TargetBinding {
+                group: binding.group,
+                binding: binding.binding,
+                name,
+            })
+            .collect();
+        bindings.sort_by(|a, b| (a.group, a.binding, &a.name).cmp(&(b.group, b.binding, &b.name)));
+        bindings.dedup();
+
+        outputs.push(TargetOutput {
+            target,
+            stages: vec![shader_stage(entry_point.stage)],
+            entry_points: vec!["main".to_string()],
+            code: TargetCode::Text(code),
+            bindings,
+        });
+    }
+
+    Ok(outputs)
+}
+
+fn translate_hlsl(module: &Module, info: &ModuleInfo, shader_model: u8) -> Result<Vec<TargetOutput>, Diagnostic> {
+    let target = CompileTarget::Hlsl { shader_model };
+    let shader_model = match shader_model {
+        50 => hlsl::ShaderModel::V5_0,
+        51 => hlsl::ShaderModel::V5_1,
+        60 => hlsl::ShaderModel::V6_0,
+        61 => hlsl::ShaderModel::V6_1,
+        62 => hlsl::ShaderModel::V6_2,
+        63 => hlsl::ShaderModel::V6_3,
+        64 => hlsl::ShaderModel::V6_4,
+        65 => hlsl::ShaderModel::V6_5,
+        66 => hlsl::ShaderModel::V6_6,
+        67 => hlsl::ShaderModel::V6_7,
+        _ => {
+            return Err(Diagnostic::error(
+                DiagnosticCode::InvalidOption,
+                format!("Unsupported HLSL shader model {}", target),
+            ))
+        }
+    };
+    let options = hlsl::Options {
+        shader_model,
+        ..hlsl::Options::default()
+    };
+
+    let mut code = String::new();
+    let reflection = hlsl::Writer::new(&mut code, &options)
+        .write(module, info, None)
+        .map_err(|error| failed(target, &error))?;
+    let entry_points = reflection
+        .entry_point_names
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| failed(target, &error))?;
+
+    Ok(vec![TargetOutput {
+        target,
+        stages: module_stages(module),
+        entry_points,
+        code: TargetCode::Text(code),
+        bindings: Vec::new(),
+    }])
+}
+
+fn translate_msl(module: &Module, info: &ModuleInfo) -> Result<Vec<TargetOutput>, Diagnostic> {
+    let target = CompileTarget::Msl;
+    let (code, translation) = msl::write_string(
+        module,
+        info,
+        &msl::Options::default(),
+        &msl::PipelineOptions::default(),
+    )
+    .map_err(|error| failed(target, &error))?;
+    let entry_points = translation
+        .entry_point_names
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| failed(target, &error))?;
+
+    Ok(vec![TargetOutput {
+        target,
+        stages: module_stages(module),
+        entry_points,
+        code: TargetCode::Text(code),
+        bindings: Vec::new(),
+    }])
+}
+
+/// Stages of the module's entry points, in order
+fn module_stages(module: &Module) -> Vec<ShaderStage> {
+    module.entry_points.iter().map(|entry_point| shader_stage(entry_point.stage)).collect()
+}
+
+fn shader_stage(stage: naga::ShaderStage) -> ShaderStage {
+    match stage {
+        naga::ShaderStage::Vertex => ShaderStage::Vertex,
+        naga::ShaderStage::Fragment => ShaderStage::Fragment,
+        naga::ShaderStage::Compute => ShaderStage::Compute,
+    }
+}
+
+/// Error for a backend that can't translate the module
+fn failed(target: CompileTarget, error: &dyn std::error::Error) -> Diagnostic {
+    Diagnostic::error(
+        DiagnosticCode::TranslationFailed,
+        format!("Can't translate the shader to {}: {}", target, validation::error_chain(error)),
+    )
+}
@@ -0,0 +1,589 @@
+//! # Type Checking
+//!
+//! Static type checking of shader graph data connections.
+//!
+//! Runs between data flow analysis and code generation so that mistyped wires
+//! are reported against the nodes and pins involved instead of surfacing as
+//! invalid WGSL.
+
+use crate::declarations::GraphDeclarations;
+use crate::metadata::ShaderMetadataProvider;
+use crate::options::EntryPointNames;
+use crate::types::{self, GenericBindings, ScalarType, WgslType};
+use crate::uniforms::{self, MaterialLayout};
+use crate::variables::{self, LocalVariable};
+use graphy::analysis::DataSource;
+use graphy::core::NodeMetadataProvider;
+use graphy::{DataResolver, GraphDescription};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use thiserror::Error;
+
+/// A kind of implicit conversion inserted between compatible types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ConversionKind {
+    /// Scalar → vector of the same scalar type, e.g. `f32` → `vec3<f32>(x)`
+    Splat,
+    /// `vec4` → `vec3` of the same scalar type, e.g. `vec4<f32>` → `x.xyz`
+    Truncate,
+    /// Integer → float, e.g. `i32` → `f32(x)` (also component-wise for vectors)
+    IntToFloat,
+    /// `vec3<f32>` color → `vec4<f32>(x, 1.0)` with an opaque alpha, only
+    /// into params marked as colors
+    AppendAlpha,
+}
+
+impl ConversionKind {
+    /// Find the conversion from one type to another, if one is allowed at all
+    ///
+    /// `to_color` tells whether the target param holds an RGBA color.
+    pub fn between(from: &WgslType, to: &WgslType, to_color: bool) -> Option<Self> {
+        use WgslType::{Scalar, Vector};
+
+        match (from, to) {
+            (Scalar(from_scalar), Vector(_, to_scalar)) if from_scalar == to_scalar => {
+                Some(Self::Splat)
+            }
+            (Vector(4, from_scalar), Vector(3, to_scalar)) if from_scalar == to_scalar => {
+                Some(Self::Truncate)
+            }
+            (Vector(3, ScalarType::F32), Vector(4, ScalarType::F32)) if to_color => {
+                Some(Self::AppendAlpha)
+            }
+            (Scalar(ScalarType::I32 | ScalarType::U32), Scalar(ScalarType::F32)) => {
+                Some(Self::IntToFloat)
+            }
+            (Vector(from_size, ScalarType::I32 | ScalarType::U32), Vector(to_size, ScalarType::F32))
+                if from_size == to_size =>
+            {
+                Some(Self::IntToFloat)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ConversionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Splat => "splat",
+            Self::Truncate => "truncation",
+            Self::IntToFloat => "int to float",
+            Self::AppendAlpha => "alpha append",
+        };
+        f.write_str(name)
+    }
+}
+
+/// How the compiler treats an implicit conversion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ConversionMode {
+    /// Insert the conversion silently
+    Allow,
+    /// Insert the conversion and report a warning
+    Warn,
+    /// Reject the connection as a type error
+    Deny,
+}
+
+/// Per-kind configuration of implicit conversions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversionPolicy {
+    pub splat: ConversionMode,
+    pub truncate: ConversionMode,
+    pub int_to_float: ConversionMode,
+    pub append_alpha: ConversionMode,
+}
+
+impl ConversionPolicy {
+    /// Policy that rejects every implicit conversion
+    pub fn strict() -> Self {
+        Self {
+            splat: ConversionMode::Deny,
+            truncate: ConversionMode::Deny,
+            int_to_float: ConversionMode::Deny,
+            append_alpha: ConversionMode::Deny,
+        }
+    }
+
+    /// Mode configured for a conversion kind
+    pub fn mode(&self, kind: ConversionKind) -> ConversionMode {
+        match kind {
+            ConversionKind::Splat => self.splat,
+            ConversionKind::Truncate => self.truncate,
+            ConversionKind::IntToFloat => self.int_to_float,
+            ConversionKind::AppendAlpha => self.append_alpha,
+        }
+    }
+}
+
+impl Default for ConversionPolicy {
+    /// Lossless conversions are allowed, truncation drops components and warns
+    fn default() -> Self {
+        Self {
+            splat: ConversionMode::Allow,
+            truncate: ConversionMode::Warn,
+            int_to_float: ConversionMode::Allow,
+            append_alpha: ConversionMode::Allow,
+        }
+    }
+}
+
+/// An implicit conversion inserted on a data connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImplicitConversion {
+    pub source_node: String,
+    pub source_pin: String,
+    pub target_node: String,
+    pub target_pin: String,
+    pub kind: ConversionKind,
+    pub from: WgslType,
+    pub to: WgslType,
+    /// Mode the conversion was inserted under (`Allow` or `Warn`)
+    pub mode: ConversionMode,
+}
+
+impl fmt::Display for ImplicitConversion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Implicit {} from {} to {} on {}.{} → {}.{}",
+            self.kind, self.from, self.to,
+            self.source_node, self.source_pin, self.target_node, self.target_pin
+        )
+    }
+}
+
+/// Result of type checking a graph
+#[derive(Debug, Clone, Default)]
+pub struct TypeCheckReport {
+    /// Type errors, ordered by target node ID
+    pub errors: Vec<TypeError>,
+    /// Inserted conversions, keyed by target node ID and pin name
+    pub conversions: BTreeMap<(String, String), ImplicitConversion>,
+    /// Resolved generic type parameters, keyed by node ID
+    pub generics: BTreeMap<String, GenericBindings>,
+    /// Local variables the graph declares, keyed by name
+    pub variables: BTreeMap<String, LocalVariable>,
+    /// Uniform buffer layout and resource bindings of the graph's parameters
+    pub material: MaterialLayout,
+}
+
+impl TypeCheckReport {
+    /// Whether the graph type checked without errors
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Conversion inserted on the given input, if any
+    pub fn conversion(&self, node_id: &str, pin_name: &str) -> Option<&ImplicitConversion> {
+        self.conversions.get(&(node_id.to_string(), pin_name.to_string()))
+    }
+
+    /// Concrete types bound to a node's generic type parameters, if it has any
+    pub fn generic_bindings(&self, node_id: &str) -> Option<&GenericBindings> {
+        self.generics.get(node_id)
+    }
+
+    /// Conversions configured to report a warning
+    pub fn warnings(&self) -> impl Iterator<Item = &ImplicitConversion> {
+        self.conversions
+            .values()
+            .filter(|conversion| conversion.mode == ConversionMode::Warn)
+    }
+}
+
+/// A type error on a data connection
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TypeError {
+    /// A connection carries a value of the wrong type
+    #[error("Type mismatch: {source_node}.{source_pin} ({found}) is connected to {target_node}.{target_pin} ({expected})")]
+    Mismatch {
+        source_node: String,
+        source_pin: String,
+        target_node: String,
+        target_pin: String,
+        expected: WgslType,
+        found: WgslType,
+    },
+
+    /// A connection needs an implicit conversion the policy denies
+    #[error("Implicit {kind} from {found} to {expected} is denied: {source_node}.{source_pin} → {target_node}.{target_pin}")]
+    ConversionDenied {
+        source_node: String,
+        source_pin: String,
+        target_node: String,
+        target_pin: String,
+        kind: ConversionKind,
+        expected: WgslType,
+        found: WgslType,
+    },
+
+    /// A connection comes from a node that doesn't produce a value
+    #[error("{source_node}.{source_pin} has no output value but is connected to {target_node}.{target_pin}")]
+    NoOutputValue {
+        source_node: String,
+        source_pin: String,
+        target_node: String,
+        target_pin: String,
+    },
+
+    /// A swizzle node's mask doesn't fit its input
+    #[error("Invalid swizzle on {node_id}: {reason}")]
+    InvalidSwizzle {
+        node_id: String,
+        mask: String,
+        reason: String,
+    },
+
+    /// A variable node names a variable the graph doesn't declare
+    #[error("Invalid local variable on {node_id}: {reason}")]
+    InvalidVariable {
+        node_id: String,
+        reason: String,
+    },
+
+    /// A graph-level variable declaration is invalid
+    #[error("Invalid declaration of local variable {name}: {reason}")]
+    InvalidVariableDeclaration {
+        name: String,
+        reason: String,
+    },
+
+    /// A parameter node names a parameter the graph doesn't declare, or one
+    /// of a type it can't read
+    #[error("Invalid parameter on {node_id}: {reason}")]
+    InvalidParameter {
+        node_id: String,
+        reason: String,
+    },
+
+    /// A graph-level parameter declaration is invalid
+    #[error("Invalid declaration of parameter {name}: {reason}")]
+    InvalidParameterDeclaration {
+        name: String,
+        reason: String,
+    },
+
+    /// A node's type isn't in the shader node registry
+    #[error("Unknown node type {node_type} for node {node_id}")]
+    UnknownNodeType {
+        node_id: String,
+        node_type: String,
+    },
+}
+
+impl TypeError {
+    /// ID of the node reporting the error (the connection's target), if the
+    /// error is about a node rather than a graph-level declaration
+    pub fn node_id(&self) -> Option<&str> {
+        match self {
+            Self::Mismatch { target_node, .. }
+            | Self::ConversionDenied { target_node, .. }
+            | Self::NoOutputValue { target_node, .. } => Some(target_node),
+            Self::InvalidSwizzle { node_id, .. }
+            | Self::InvalidVariable { node_id, .. }
+            | Self::InvalidParameter { node_id, .. }
+            | Self::UnknownNodeType { node_id, .. } => Some(node_id),
+            Self::InvalidVariableDeclaration { .. }
+            | Self::InvalidParameterDeclaration { .. } => None,
+        }
+    }
+}
+
+/// Type checker for shader graph data connections
+pub struct TypeChecker<'a> {
+    graph: &'a GraphDescription,
+    metadata_provider: &'a ShaderMetadataProvider,
+    data_resolver: &'a DataResolver,
+    policy: ConversionPolicy,
+    material_group: u32,
+    entry_points: EntryPointNames,
+    declarations: GraphDeclarations,
+}
+
+impl<'a> TypeChecker<'a> {
+    pub fn new(
+        graph: &'a GraphDescription,
+        metadata_provider: &'a ShaderMetadataProvider,
+        data_resolver: &'a DataResolver,
+    ) -> Self {
+        Self {
+            graph,
+            metadata_provider,
+            data_resolver,
+            policy: ConversionPolicy::default(),
+            material_group: uniforms::MATERIAL_GROUP,
+            entry_points: EntryPointNames::default(),
+            declarations: GraphDeclarations::default(),
+        }
+    }
+
+    /// Set the policy for implicit conversions
+    pub fn with_policy(mut self, policy: ConversionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set the bind group material parameters are bound in
+    pub fn with_material_group(mut self, group: u32) -> Self {
+        self.material_group = group;
+        self
+    }
+
+    /// Set the entry point names that variables and parameters must not reuse
+    pub fn with_entry_points(mut self, entry_points: EntryPointNames) -> Self {
+        self.entry_points = entry_points;
+        self
+    }
+
+    /// Set the graph-level declarations the graph's nodes refer to
+    pub fn with_declarations(mut self, declarations: GraphDeclarations) -> Self {
+        self.declarations = declarations;
+        self
+    }
+
+    /// Check every data connection in the graph
+    ///
+    /// Generic params are checked against the concrete types resolved from
+    /// the node's connected inputs. Connections between compatible types get
+    /// an implicit conversion according to the policy; all other mismatches
+    /// are reported as errors.
+    pub fn check(&self) -> TypeCheckReport {
+        let mut report = TypeCheckReport::default();
+        let errors = &mut report.errors;
+
+        let mut nodes: Vec<_> = self.graph.nodes.values().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let material = uniforms::collect_parameters(
+            self.graph,
+            &self.declarations,
+            self.metadata_provider,
+            self.material_group,
+            &self.entry_points,
+            errors,
+        );
+        let variables = variables::collect_variables(
+            self.graph,
+            &self.declarations,
+            self.metadata_provider,
+            &material,
+            &self.entry_points,
+            errors,
+        );
+
+        let mut generics = BTreeMap::new();
+        for node in &nodes {
+            self.resolve_generics(&node.id, &variables, &material, &mut generics, &mut HashSet::new());
+        }
+        let no_bindings = GenericBindings::new();
+
+        for node in nodes {
+            let Some(node_meta) = self.metadata_provider.get_node_metadata(&node.node_type) else {
+                errors.push(TypeError::UnknownNodeType {
+                    node_id: node.id.clone(),
+                    node_type: node.node_type.clone(),
+                });
+                continue;
+            };
+
+            for param in &node_meta.params {
+                let Some(DataSource::Connection { source_node_id, source_pin }) =
+                    self.data_resolver.get_input_source(&node.id, &param.name)
+                else {
+                    continue;
+                };
+
+                let bindings = generics.get(&node.id).unwrap_or(&no_bindings);
+                let source_bindings = generics.get(source_node_id).unwrap_or(&no_bindings);
+                let expected = types::resolved_param_type(param, bindings);
+                let to_color = self.metadata_provider
+                    .get_node_info(&node.node_type)
+                    .is_some_and(|info| info.is_color_param(&param.name));
+                match self.output_type(source_node_id, source_pin, source_bindings) {
+                    Some(found) if found == expected => {}
+                    Some(found) => match ConversionKind::between(&found, &expected, to_color) {
+                        Some(kind) if self.policy.mode(kind) != ConversionMode::Deny => {
+                            report.conversions.insert(
+                                (node.id.clone(), param.name.clone()),
+                                ImplicitConversion {
+                                    source_node: source_node_id.clone(),
+                                    source_pin: source_pin.clone(),
+                                    target_node: node.id.clone(),
+                                    target_pin: param.name.clone(),
+                                    kind,
+                                    from: found,
+                                    to: expected,
+                                    mode: self.policy.mode(kind),
+                                },
+                            );
+                        }
+                        Some(kind) => errors.push(TypeError::ConversionDenied {
+                            source_node: source_node_id.clone(),
+                            source_pin: source_pin.clone(),
+                            target_node: node.id.clone(),
+                            target_pin: param.name.clone(),
+                            kind,
+                            expected,
+                            found,
+                        }),
+                        None => errors.push(TypeError::Mismatch {
+                            source_node: source_node_id.clone(),
+                            source_pin: source_pin.clone(),
+                            target_node: node.id.clone(),
+                            target_pin: param.name.clone(),
+                            expected,
+                            found,
+                        }),
+                    },
+                    // Invalid swizzles are reported on the swizzle node itself
+                    None if self.is_swizzle(source_node_id) => {}
+                    None => errors.push(TypeError::NoOutputValue {
+                        source_node: source_node_id.clone(),
+                        source_pin: source_pin.clone(),
+                        target_node: node.id.clone(),
+                        target_pin: param.name.clone(),
+                    }),
+                }
+            }
+
+            if let Some(mask) = self.metadata_provider.get_swizzle_mask(node) {
+                let bindings = generics.get(&node.id).unwrap_or(&no_bindings);
+                let swizzled = types::resolved_return_type(node_meta, bindings)
+                    .ok_or_else(|| "node has no value to swizzle".to_string())
+                    .and_then(|value_type| value_type.swizzle(&mask));
+                if let Err(reason) = swizzled {
+                    errors.push(TypeError::InvalidSwizzle {
+                        node_id: node.id.clone(),
+                        mask,
+                        reason,
+                    });
+                }
+            }
+        }
+
+        report.generics = generics;
+        report.variables = variables;
+        report.material = material;
+        report
+    }
+
+    /// Resolve a node's generic type parameters, resolving upstream nodes first
+    ///
+    /// Each generic takes the widest allowed type among the connected inputs
+    /// that use it, so narrower inputs (e.g. the scalar in vector * scalar) are
+    /// splatted by implicit conversions. Generics without a connected input
+    /// take their first allowed type. Variable and parameter nodes bind their
+    /// generic to the type of the variable or parameter they read.
+    fn resolve_generics(
+        &self,
+        node_id: &str,
+        variables: &BTreeMap<String, LocalVariable>,
+        material: &MaterialLayout,
+        resolved: &mut BTreeMap<String, GenericBindings>,
+        visiting: &mut HashSet<String>,
+    ) -> GenericBindings {
+        if let Some(bindings) = resolved.get(node_id) {
+            return bindings.clone();
+        }
+
+        let mut bindings = GenericBindings::new();
+        let Some(node) = self.graph.nodes.get(node_id) else {
+            return bindings;
+        };
+        let (Some(node_meta), Some(node_info)) = (
+            self.metadata_provider.get_node_metadata(&node.node_type),
+            self.metadata_provider.get_node_info(&node.node_type),
+        ) else {
+            return bindings;
+        };
+
+        if let Some(generic) = &node_info.variable_generic {
+            let variable = variables::variable_name(node, self.metadata_provider)
+                .and_then(|name| variables.get(name));
+            if let Some(variable) = variable {
+                bindings.insert(generic.clone(), variable.var_type.clone());
+            }
+            resolved.insert(node_id.to_string(), bindings.clone());
+            return bindings;
+        }
+
+        if let Some(generic) = &node_info.parameter_generic {
+            let parameter_type = uniforms::parameter_name(node, self.metadata_provider)
+                .and_then(|name| material.parameter_type(name));
+            if let Some(parameter_type) = parameter_type {
+                bindings.insert(generic.clone(), parameter_type.clone());
+            }
+            resolved.insert(node_id.to_string(), bindings.clone());
+            return bindings;
+        }
+
+        // Nodes without generics need no resolution, cycles are reported elsewhere
+        if node_info.generics.is_empty() || !visiting.insert(node_id.to_string()) {
+            return bindings;
+        }
+
+        for (name, allowed) in &node_info.generics {
+            let generic = WgslType::Other(name.clone());
+            let allowed: Vec<WgslType> = allowed.iter().map(|t| WgslType::parse(t)).collect();
+
+            let mut widest: Option<WgslType> = None;
+            for param in node_meta.params.iter().filter(|param| types::param_type(param) == generic) {
+                let Some(DataSource::Connection { source_node_id, source_pin }) =
+                    self.data_resolver.get_input_source(node_id, &param.name)
+                else {
+                    continue;
+                };
+
+                let source_bindings = self.resolve_generics(source_node_id, variables, material, resolved, visiting);
+                let Some(found) = self.output_type(source_node_id, source_pin, &source_bindings) else {
+                    continue;
+                };
+
+                let is_wider = widest
+                    .as_ref()
+                    .map_or(true, |current| found.component_count() > current.component_count());
+                if allowed.contains(&found) && is_wider {
+                    widest = Some(found);
+                }
+            }
+
+            if let Some(concrete) = widest.or_else(|| allowed.first().cloned()) {
+                bindings.insert(name.clone(), concrete);
+            }
+        }
+
+        visiting.remove(node_id);
+        resolved.insert(node_id.to_string(), bindings.clone());
+        bindings
+    }
+
+    /// Type of the value read from a node's output pin
+    ///
+    /// Named outputs have their declared type; any other pin carries the
+    /// node's return value, swizzled for swizzle nodes.
+    fn output_type(&self, node_id: &str, source_pin: &str, bindings: &GenericBindings) -> Option<WgslType> {
+        let node = self.graph.nodes.get(node_id)?;
+        let node_meta = self.metadata_provider.get_node_metadata(&node.node_type)?;
+
+        if let Some(output) = self.metadata_provider.get_node_output(node, source_pin) {
+            return Some(WgslType::parse(&output.output_type).resolve(bindings));
+        }
+
+        let value_type = types::resolved_return_type(node_meta, bindings)?;
+        match self.metadata_provider.get_swizzle_mask(node) {
+            Some(mask) => value_type.swizzle(&mask).ok(),
+            None => Some(value_type),
+        }
+    }
+
+    /// Whether a node swizzles its value with a mask property
+    fn is_swizzle(&self, node_id: &str) -> bool {
+        self.graph
+            .nodes
+            .get(node_id)
+            .is_some_and(|node| self.metadata_provider.get_swizzle_mask(node).is_some())
+    }
+}
@@ -0,0 +1,260 @@
+//! # WGSL Types
+//!
+//! Parsing and classification of the WGSL type strings used in node metadata.
+
+use graphy::core::{NodeMetadata, ParamInfo};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Concrete types bound to a node's generic type parameters, keyed by name
+pub type GenericBindings = BTreeMap<String, WgslType>;
+
+/// WGSL scalar type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScalarType {
+    F32,
+    I32,
+    U32,
+    Bool,
+}
+
+impl ScalarType {
+    /// Parse a scalar type name (`f32`, `i32`, `u32`, `bool`)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "f32" => Some(Self::F32),
+            "i32" => Some(Self::I32),
+            "u32" => Some(Self::U32),
+            "bool" => Some(Self::Bool),
+            _ => None,
+        }
+    }
+
+    /// Zero value literal of this scalar type
+    pub fn zero_value(&self) -> &'static str {
+        match self {
+            Self::F32 => "0.0",
+            Self::I32 => "0i",
+            Self::U32 => "0u",
+            Self::Bool => "false",
+        }
+    }
+}
+
+impl fmt::Display for ScalarType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::F32 => "f32",
+            Self::I32 => "i32",
+            Self::U32 => "u32",
+            Self::Bool => "bool",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A WGSL type as used by node params and return types
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WgslType {
+    /// `f32`, `i32`, `u32` or `bool`
+    Scalar(ScalarType),
+    /// `vecN<T>` with N in 2..=4
+    Vector(u8, ScalarType),
+    /// `matCxR<f32>` with C columns and R rows
+    Matrix(u8, u8),
+    /// Any `texture_*` type, stored as written
+    Texture(String),
+    /// `sampler`
+    Sampler,
+    /// `sampler_comparison`
+    SamplerComparison,
+    /// Any other type (structs, arrays, ...), stored as written
+    Other(String),
+}
+
+impl WgslType {
+    /// Parse a WGSL type string
+    ///
+    /// Accepts both the generic (`vec3<f32>`) and shorthand (`vec3f`) spellings.
+    pub fn parse(type_string: &str) -> Self {
+        let type_string: String = type_string.chars().filter(|c| !c.is_whitespace()).collect();
+
+        if let Some(scalar) = ScalarType::parse(&type_string) {
+            return Self::Scalar(scalar);
+        }
+
+        if let Some(rest) = type_string.strip_prefix("vec") {
+            if let Some((size, scalar)) = parse_sized_type(rest) {
+                if (2..=4).contains(&size) {
+                    return Self::Vector(size, scalar);
+                }
+            }
+        }
+
+        if let Some(rest) = type_string.strip_prefix("mat") {
+            let mut parts = rest.splitn(2, 'x');
+            let columns = parts.next().and_then(|c| c.parse::<u8>().ok());
+            let rows = parts.next().and_then(parse_sized_type);
+            if let (Some(columns), Some((rows, ScalarType::F32))) = (columns, rows) {
+                if (2..=4).contains(&columns) && (2..=4).contains(&rows) {
+                    return Self::Matrix(columns, rows);
+                }
+            }
+        }
+
+        match type_string.as_str() {
+            "sampler" => Self::Sampler,
+            "sampler_comparison" => Self::SamplerComparison,
+            s if s.starts_with("texture_") => Self::Texture(type_string),
+            _ => Self::Other(type_string),
+        }
+    }
+
+    /// Whether values of this type are resources that can only come from a binding
+    pub fn is_resource(&self) -> bool {
+        matches!(self, Self::Texture(_) | Self::Sampler | Self::SamplerComparison)
+    }
+
+    /// Zero value expression of this type, or `None` for resources
+    pub fn zero_value(&self) -> Option<String> {
+        match self {
+            Self::Scalar(scalar) => Some(scalar.zero_value().to_string()),
+            Self::Texture(_) | Self::Sampler | Self::SamplerComparison => None,
+            other => Some(format!("{}()", other)),
+        }
+    }
+
+    /// Scalar type of a scalar or vector
+    pub fn scalar_type(&self) -> Option<ScalarType> {
+        match self {
+            Self::Scalar(scalar) | Self::Vector(_, scalar) => Some(*scalar),
+            Self::Matrix(_, _) => Some(ScalarType::F32),
+            _ => None,
+        }
+    }
+
+    /// Short name used to tell generic instantiations apart (`f32`, `vec3f`, ...)
+    pub fn short_name(&self) -> String {
+        let suffix = |scalar: &ScalarType| match scalar {
+            ScalarType::Bool => "b",
+            ScalarType::F32 => "f",
+            ScalarType::I32 => "i",
+            ScalarType::U32 => "u",
+        };
+
+        match self {
+            Self::Scalar(scalar) => scalar.to_string(),
+            Self::Vector(size, scalar) => format!("vec{}{}", size, suffix(scalar)),
+            Self::Matrix(columns, rows) => format!("mat{}x{}f", columns, rows),
+            other => other
+                .to_string()
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect(),
+        }
+    }
+
+    /// Replace a generic type parameter with its bound concrete type
+    pub fn resolve(self, bindings: &GenericBindings) -> Self {
+        match self {
+            Self::Other(name) => bindings.get(&name).cloned().unwrap_or(Self::Other(name)),
+            other => other,
+        }
+    }
+
+    /// Type produced by swizzling a vector with a mask such as `xy` or `bgr`
+    ///
+    /// Masks use either `xyzw` or `rgba` components, up to four of them, and
+    /// may only read components the vector has.
+    pub fn swizzle(&self, mask: &str) -> Result<Self, String> {
+        let Self::Vector(size, scalar) = self else {
+            return Err(format!("values of type {} can't be swizzled", self));
+        };
+
+        if mask.is_empty() || mask.len() > 4 {
+            return Err(format!("swizzle mask '{}' must have 1 to 4 components", mask));
+        }
+
+        let indices: Option<Vec<usize>> = ["xyzw", "rgba"]
+            .iter()
+            .map(|set| mask.chars().map(|c| set.find(c)).collect::<Option<Vec<_>>>())
+            .find(Option::is_some)
+            .flatten();
+        let Some(indices) = indices else {
+            return Err(format!("'{}' is not a swizzle mask (use xyzw or rgba components)", mask));
+        };
+
+        if indices.iter().any(|&index| index >= *size as usize) {
+            return Err(format!("swizzle mask '{}' reads past the components of {}", mask, self));
+        }
+
+        Ok(match indices.len() {
+            1 => Self::Scalar(*scalar),
+            len => Self::Vector(len as u8, *scalar),
+        })
+    }
+
+    /// Number of components of a scalar (1) or vector (N)
+    pub fn component_count(&self) -> Option<u8> {
+        match self {
+            Self::Scalar(_) => Some(1),
+            Self::Vector(size, _) => Some(*size),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for WgslType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Scalar(scalar) => write!(f, "{}", scalar),
+            Self::Vector(size, scalar) => write!(f, "vec{}<{}>", size, scalar),
+            Self::Matrix(columns, rows) => write!(f, "mat{}x{}<f32>", columns, rows),
+            Self::Texture(name) | Self::Other(name) => f.write_str(name),
+            Self::Sampler => f.write_str("sampler"),
+            Self::SamplerComparison => f.write_str("sampler_comparison"),
+        }
+    }
+}
+
+/// Parse the `N<T>` or `NT` tail of a vector or matrix type
+fn parse_sized_type(rest: &str) -> Option<(u8, ScalarType)> {
+    let size = rest.chars().next()?.to_digit(10)? as u8;
+    let tail = &rest[1..];
+
+    let scalar = if let Some(inner) = tail.strip_prefix('<').and_then(|t| t.strip_suffix('>')) {
+        ScalarType::parse(inner)?
+    } else {
+        match tail {
+            "f" => ScalarType::F32,
+            "i" => ScalarType::I32,
+            "u" => ScalarType::U32,
+            _ => return None,
+        }
+    };
+
+    Some((size, scalar))
+}
+
+/// WGSL type of a node param
+pub fn param_type(param: &ParamInfo) -> WgslType {
+    WgslType::parse(&param.param_type)
+}
+
+/// WGSL return type of a node, if it returns a value
+pub fn return_type(node_meta: &NodeMetadata) -> Option<WgslType> {
+    node_meta
+        .return_type
+        .as_ref()
+        .map(|return_type| WgslType::parse(&return_type.type_string))
+}
+
+/// WGSL type of a node param with its generic type parameters resolved
+pub fn resolved_param_type(param: &ParamInfo, bindings: &GenericBindings) -> WgslType {
+    param_type(param).resolve(bindings)
+}
+
+/// WGSL return type of a node with its generic type parameters resolved
+pub fn resolved_return_type(node_meta: &NodeMetadata, bindings: &GenericBindings) -> Option<WgslType> {
+    return_type(node_meta).map(|return_type| return_type.resolve(bindings))
+}
@@ -0,0 +1,420 @@
+//! # Material Parameters
+//!
+//! Exposed graph parameters and the bindings they are read from.
+//!
+//! Each parameter is declared once in the graph's
+//! [`GraphDeclarations`](crate::declarations::GraphDeclarations), with its
+//! name, type and optional default value for new material instances.
+//! Parameter nodes only name the parameter they read in their `parameter`
+//! property, and several nodes may read the same parameter.
+//!
+//! Value parameters (`float`, `int`, `bool`, `color` or a WGSL scalar/vector
+//! type) are packed into the `MaterialParams` uniform buffer. Texture and
+//! sampler parameters each get their own binding, allocated after the
+//! uniform buffer: textures first, then samplers, each sorted by name.
+//! A sampler is declared as `filtering`, `non_filtering` or `comparison`,
+//! which sets the binding type reflection reports for it.
+
+use crate::codegen::literals;
+use crate::declarations::GraphDeclarations;
+use crate::identifiers;
+use crate::metadata::ShaderMetadataProvider;
+use crate::options::EntryPointNames;
+use crate::reflection::SamplerBindingType;
+use crate::typecheck::TypeError;
+use crate::types::{ScalarType, WgslType};
+use graphy::{GraphDescription, NodeInstance, NodeMetadataProvider, PropertyValue};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Property holding the name of the parameter a node reads
+pub const PARAMETER_PROPERTY: &str = "parameter";
+
+/// Name of the generated uniform struct
+pub const UNIFORM_STRUCT_NAME: &str = "MaterialParams";
+
+/// Name of the generated uniform variable
+pub const UNIFORM_VARIABLE_NAME: &str = "material";
+
+/// Default bind group of the material uniform buffer and resources
+pub const MATERIAL_GROUP: u32 = 0;
+
+/// Binding of the material uniform buffer within its group
+pub const MATERIAL_BINDING: u32 = 0;
+
+/// A field of the material uniform struct
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformField {
+    pub name: String,
+    /// Type the parameter has in the graph
+    pub value_type: WgslType,
+    /// Type stored in the buffer (`u32` for `bool`, which isn't host-shareable)
+    pub storage_type: WgslType,
+    /// Byte offset within the struct
+    pub offset: u32,
+    /// Size in bytes
+    pub size: u32,
+    /// Required alignment in bytes
+    pub align: u32,
+    /// Default value as declared
+    pub default: Option<String>,
+}
+
+impl UniformField {
+    /// WGSL expression reading the field as its graph type
+    pub fn read_expression(&self) -> String {
+        let access = format!("{}.{}", UNIFORM_VARIABLE_NAME, self.name);
+        if self.value_type == self.storage_type {
+            access
+        } else {
+            format!("{} != 0u", access)
+        }
+    }
+}
+
+/// Layout of the material uniform struct
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UniformLayout {
+    /// Fields in declaration order, largest alignment first
+    pub fields: Vec<UniformField>,
+    /// Size of the struct in bytes, rounded up to 16
+    pub size: u32,
+}
+
+impl UniformLayout {
+    /// Whether the graph exposes no value parameters
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Field for the parameter with the given name
+    pub fn field(&self, name: &str) -> Option<&UniformField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// A texture or sampler parameter bound as its own global
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceBinding {
+    pub name: String,
+    /// `texture_*`, `sampler` or `sampler_comparison`
+    pub resource_type: WgslType,
+    /// Binding type of a sampler parameter, `None` for textures
+    pub sampler_binding_type: Option<SamplerBindingType>,
+    pub group: u32,
+    pub binding: u32,
+}
+
+/// Everything a material binds for the graph's parameters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaterialLayout {
+    /// Value parameters packed into the uniform buffer
+    pub uniforms: UniformLayout,
+    /// Texture and sampler parameters, in binding order
+    pub resources: Vec<ResourceBinding>,
+    /// Bind group of the uniform buffer and every resource
+    pub group: u32,
+}
+
+impl MaterialLayout {
+    /// Whether the graph exposes no parameters at all
+    pub fn is_empty(&self) -> bool {
+        self.uniforms.is_empty() && self.resources.is_empty()
+    }
+
+    /// Resource binding for the parameter with the given name
+    pub fn resource(&self, name: &str) -> Option<&ResourceBinding> {
+        self.resources.iter().find(|resource| resource.name == name)
+    }
+
+    /// Type the parameter with the given name has in the graph
+    pub fn parameter_type(&self, name: &str) -> Option<&WgslType> {
+        self.uniforms
+            .field(name)
+            .map(|field| &field.value_type)
+            .or_else(|| self.resource(name).map(|resource| &resource.resource_type))
+    }
+
+    /// WGSL expression reading the parameter with the given name
+    pub fn read_expression(&self, name: &str) -> Option<String> {
+        self.uniforms
+            .field(name)
+            .map(UniformField::read_expression)
+            .or_else(|| self.resource(name).map(|resource| resource.name.clone()))
+    }
+
+    /// Generate the uniform struct and the binding declarations
+    ///
+    /// With `comments`, each uniform field is followed by its byte offset.
+    pub fn generate_wgsl(&self, comments: bool) -> String {
+        let mut code = String::new();
+
+        if !self.uniforms.is_empty() {
+            code.push_str(&format!("struct {} {{\n", UNIFORM_STRUCT_NAME));
+            for field in &self.uniforms.fields {
+                code.push_str(&format!("    {}: {},", field.name, field.storage_type));
+                if comments {
+                    code.push_str(&format!(" // offset {}", field.offset));
+                }
+                code.push_str("\n");
+            }
+            code.push_str("}\n\n");
+            code.push_str(&format!(
+                "@group({}) @binding({}) var<uniform> {}: {};\n",
+                self.group, MATERIAL_BINDING, UNIFORM_VARIABLE_NAME, UNIFORM_STRUCT_NAME
+            ));
+        }
+
+        for resource in &self.resources {
+            code.push_str(&format!(
+                "@group({}) @binding({}) var {}: {};\n",
+                resource.group, resource.binding, resource.name, resource.resource_type
+            ));
+        }
+
+        code
+    }
+}
+
+/// Name of the parameter read by a parameter node, if the node is one
+pub fn parameter_name<'n>(
+    node: &'n NodeInstance,
+    metadata_provider: &ShaderMetadataProvider,
+) -> Option<&'n str> {
+    metadata_provider
+        .get_node_info(&node.node_type)?
+        .parameter_generic
+        .as_ref()?;
+
+    match node.properties.get(PARAMETER_PROPERTY) {
+        Some(PropertyValue::String(name)) => Some(name.trim()),
+        _ => Some(""),
+    }
+}
+
+/// Parse a parameter type, accepting the `float`, `int`, `color`,
+/// `filtering`, `non_filtering` and `comparison` aliases
+pub fn parse_parameter_type(type_string: &str) -> WgslType {
+    match type_string.trim() {
+        "float" => WgslType::Scalar(ScalarType::F32),
+        "int" => WgslType::Scalar(ScalarType::I32),
+        "color" => WgslType::Vector(4, ScalarType::F32),
+        "filtering" | "non_filtering" => WgslType::Sampler,
+        "comparison" => WgslType::SamplerComparison,
+        other => WgslType::parse(other),
+    }
+}
+
+/// Binding type selected by a sampler parameter type, `None` for other types
+///
+/// A plain `sampler` is filtering.
+pub fn parse_sampler_binding_type(type_string: &str) -> Option<SamplerBindingType> {
+    match type_string.trim() {
+        "filtering" | "sampler" => Some(SamplerBindingType::Filtering),
+        "non_filtering" => Some(SamplerBindingType::NonFiltering),
+        "comparison" | "sampler_comparison" => Some(SamplerBindingType::Comparison),
+        _ => None,
+    }
+}
+
+/// Whether values of a type can be stored in the uniform buffer
+fn is_uniform_type(value_type: &WgslType) -> bool {
+    match value_type {
+        WgslType::Scalar(_) => true,
+        WgslType::Vector(_, scalar) => *scalar != ScalarType::Bool,
+        _ => false,
+    }
+}
+
+/// Alignment and size of a host-shareable scalar or vector in a uniform buffer
+fn align_and_size(storage_type: &WgslType) -> (u32, u32) {
+    match storage_type {
+        WgslType::Vector(2, _) => (8, 8),
+        WgslType::Vector(3, _) => (16, 12),
+        WgslType::Vector(_, _) => (16, 16),
+        _ => (4, 4),
+    }
+}
+
+/// Round `value` up to a multiple of `align`
+fn round_up(value: u32, align: u32) -> u32 {
+    value.div_ceil(align) * align
+}
+
+/// Types a parameter node type can read: its generic's type list, or any
+/// uniform type when the node type doesn't restrict its generic
+fn allowed_types(node_type: &str, metadata_provider: &ShaderMetadataProvider) -> Option<Vec<WgslType>> {
+    metadata_provider
+        .get_node_info(node_type)
+        .and_then(|info| info.generic(info.parameter_generic.as_deref()?))
+        .map(|types| types.iter().map(|t| WgslType::parse(t)).collect())
+}
+
+/// Whether a node of the given type can read parameters of `value_type`
+fn node_reads_type(node_type: &str, value_type: &WgslType, metadata_provider: &ShaderMetadataProvider) -> bool {
+    match allowed_types(node_type, metadata_provider) {
+        Some(allowed) => allowed.contains(value_type),
+        None => is_uniform_type(value_type),
+    }
+}
+
+/// Collect the parameters the graph declares and lay them out
+///
+/// Every declared parameter is part of the layout, whether or not a node
+/// reads it. A declared type must be readable by one of the parameter node
+/// types, and each parameter node must read a declared parameter its type
+/// can read. Uniform fields are ordered by alignment (largest first), then by
+/// name, which keeps padding small and the layout stable. Everything is bound
+/// in `group`. Names must pass [`identifiers::check_name`].
+pub fn collect_parameters(
+    graph: &GraphDescription,
+    declarations: &GraphDeclarations,
+    metadata_provider: &ShaderMetadataProvider,
+    group: u32,
+    entry_points: &EntryPointNames,
+    errors: &mut Vec<TypeError>,
+) -> MaterialLayout {
+    let parameter_node_types: Vec<&str> = metadata_provider
+        .get_all_nodes()
+        .into_iter()
+        .map(|node_meta| node_meta.name.as_str())
+        .filter(|name| {
+            metadata_provider
+                .get_node_info(name)
+                .is_some_and(|info| info.parameter_generic.is_some())
+        })
+        .collect();
+
+    let mut declared: BTreeMap<String, (WgslType, Option<String>)> = BTreeMap::new();
+    let mut names = BTreeSet::new();
+    let mut sampler_binding_types = BTreeMap::new();
+
+    for declaration in &declarations.parameters {
+        let name = declaration.name.trim();
+        let first_declaration = names.insert(name);
+        let mut invalid = |reason: String| {
+            errors.push(TypeError::InvalidParameterDeclaration {
+                name: name.to_string(),
+                reason,
+            });
+        };
+
+        if let Err(reason) = identifiers::check_name(name, entry_points) {
+            invalid(format!("invalid parameter name: {}", reason));
+            continue;
+        }
+        if !first_declaration {
+            invalid(format!("{} is declared more than once", name));
+            continue;
+        }
+
+        let value_type = parse_parameter_type(&declaration.param_type);
+        let readable = parameter_node_types
+            .iter()
+            .any(|node_type| node_reads_type(node_type, &value_type, metadata_provider));
+        if !readable {
+            invalid(format!("parameters of type {} are not supported", declaration.param_type.trim()));
+            continue;
+        }
+
+        if let Some(default) = &declaration.default {
+            let formatted = if value_type.is_resource() {
+                Err("texture and sampler parameters have no default".to_string())
+            } else {
+                literals::format_literal(default, &value_type)
+            };
+            if let Err(reason) = formatted {
+                invalid(format!("invalid default: {}", reason));
+                continue;
+            }
+        }
+
+        if let Some(binding_type) = parse_sampler_binding_type(&declaration.param_type) {
+            sampler_binding_types.insert(name.to_string(), binding_type);
+        }
+        declared.insert(name.to_string(), (value_type, declaration.default.clone()));
+    }
+
+    let mut nodes: Vec<_> = graph.nodes.values().collect();
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    for node in nodes {
+        let Some(name) = parameter_name(node, metadata_provider) else {
+            continue;
+        };
+
+        // Invalid declarations are reported on their own
+        let reason = match declared.get(name) {
+            None if names.contains(name) => continue,
+            None => format!("'{}' is not a declared parameter of the graph", name),
+            Some((value_type, _)) if !node_reads_type(&node.node_type, value_type, metadata_provider) => {
+                format!("{} nodes can't read {}, a {} parameter", node.node_type, name, value_type)
+            }
+            Some(_) => continue,
+        };
+        errors.push(TypeError::InvalidParameter {
+            node_id: node.id.clone(),
+            reason,
+        });
+    }
+
+    let (resources, values): (Vec<_>, Vec<_>) = declared
+        .into_iter()
+        .partition(|(_, (value_type, _))| value_type.is_resource());
+
+    let uniforms = layout_uniforms(values);
+
+    let mut resources: Vec<ResourceBinding> = resources
+        .into_iter()
+        .map(|(name, (resource_type, _))| ResourceBinding {
+            sampler_binding_type: sampler_binding_types.get(&name).copied(),
+            name,
+            resource_type,
+            group,
+            binding: 0,
+        })
+        .collect();
+    let is_texture = |resource: &ResourceBinding| matches!(resource.resource_type, WgslType::Texture(_));
+    resources.sort_by(|a, b| is_texture(b).cmp(&is_texture(a)).then_with(|| a.name.cmp(&b.name)));
+
+    let first_binding = if uniforms.is_empty() { MATERIAL_BINDING } else { MATERIAL_BINDING + 1 };
+    for (binding, resource) in (first_binding..).zip(&mut resources) {
+        resource.binding = binding;
+    }
+
+    MaterialLayout { uniforms, resources, group }
+}
+
+/// Lay out value parameters with WGSL uniform alignment
+fn layout_uniforms(values: Vec<(String, (WgslType, Option<String>))>) -> UniformLayout {
+    let mut fields: Vec<UniformField> = values
+        .into_iter()
+        .map(|(name, (value_type, default))| {
+            let storage_type = match value_type {
+                WgslType::Scalar(ScalarType::Bool) => WgslType::Scalar(ScalarType::U32),
+                ref other => other.clone(),
+            };
+            let (align, size) = align_and_size(&storage_type);
+            UniformField {
+                name,
+                value_type,
+                storage_type,
+                offset: 0,
+                size,
+                align,
+                default,
+            }
+        })
+        .collect();
+    fields.sort_by(|a, b| b.align.cmp(&a.align).then_with(|| a.name.cmp(&b.name)));
+
+    let mut offset = 0;
+    for field in &mut fields {
+        field.offset = round_up(offset, field.align);
+        offset = field.offset + field.size;
+    }
+
+    UniformLayout {
+        fields,
+        size: round_up(offset, 16),
+    }
+}
@@ -0,0 +1,73 @@
+//! # WGSL Validation
+//!
+//! Parses and validates generated WGSL with naga, so code generation bugs
+//! surface as diagnostics instead of as shader creation failures at runtime.
+//!
+//! Only available with the `validation` feature.
+
+use crate::codegen::GeneratedCode;
+use crate::diagnostics::{Diagnostic, DiagnosticCode, SourceSpan};
+use naga::valid::{Capabilities, ModuleInfo, ValidationFlags, Validator};
+use naga::SourceLocation;
+
+/// Validate generated code, reporting errors on the nodes that produced the offending lines
+///
+/// Returns the parsed module and its validation info on success.
+pub(crate) fn validate(code: &GeneratedCode) -> Result<(naga::Module, ModuleInfo), Vec<Diagnostic>> {
+    let module = naga::front::wgsl::parse_str(&code.wgsl).map_err(|error| {
+        let location = error.location(&code.wgsl);
+        vec![located(
+            Diagnostic::error(
+                DiagnosticCode::InvalidWgsl,
+                format!("Generated WGSL doesn't parse: {}", error.message()),
+            ),
+            location,
+            code,
+        )]
+    })?;
+
+    let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|error| {
+            let location = error.location(&code.wgsl);
+            vec![located(
+                Diagnostic::error(
+                    DiagnosticCode::InvalidWgsl,
+                    format!("Generated WGSL is invalid: {}", error_chain(error.as_inner())),
+                ),
+                location,
+                code,
+            )]
+        })?;
+
+    Ok((module, info))
+}
+
+/// Add the span of a naga error, and the node of its line, to a diagnostic
+fn located(diagnostic: Diagnostic, location: Option<SourceLocation>, code: &GeneratedCode) -> Diagnostic {
+    let Some(location) = location else {
+        return diagnostic;
+    };
+
+    let diagnostic = diagnostic.with_span(SourceSpan {
+        line: location.line_number,
+        column: location.line_position,
+        length: location.length,
+    });
+    match code.node_at_line(location.line_number as usize) {
+        Some(node_id) => diagnostic.with_node(node_id),
+        None => diagnostic,
+    }
+}
+
+/// Message of an error followed by the messages of its sources
+pub(crate) fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
@@ -0,0 +1,128 @@
+//! # Local Variables
+//!
+//! Mutable local variables accessed by `set_variable`/`get_variable` nodes.
+//!
+//! Each variable is declared once in the graph's
+//! [`GraphDeclarations`](crate::declarations::GraphDeclarations), with its
+//! name, WGSL type and optional initial value. The nodes accessing the
+//! variable only name it in their `variable` property.
+
+use crate::codegen::literals;
+use crate::declarations::GraphDeclarations;
+use crate::identifiers;
+use crate::metadata::ShaderMetadataProvider;
+use crate::options::EntryPointNames;
+use crate::typecheck::TypeError;
+use crate::uniforms::MaterialLayout;
+use crate::types::WgslType;
+use graphy::{GraphDescription, NodeInstance, PropertyValue};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Property holding the name of the variable a node accesses
+pub const VARIABLE_PROPERTY: &str = "variable";
+
+/// A mutable local variable of the entry function
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalVariable {
+    pub name: String,
+    pub var_type: WgslType,
+    /// Starting value as declared, before literal formatting
+    pub initial_value: Option<String>,
+}
+
+/// Name of the variable accessed by a variable node, if the node is one
+pub fn variable_name<'n>(
+    node: &'n NodeInstance,
+    metadata_provider: &ShaderMetadataProvider,
+) -> Option<&'n str> {
+    metadata_provider
+        .get_node_info(&node.node_type)?
+        .variable_generic
+        .as_ref()?;
+
+    match node.properties.get(VARIABLE_PROPERTY) {
+        Some(PropertyValue::String(name)) => Some(name.trim()),
+        _ => Some(""),
+    }
+}
+
+/// Collect the variables the graph declares
+///
+/// Reports declarations with invalid names, unsupported types, invalid
+/// initial values or a name declared twice, and variable nodes that access
+/// a variable the graph doesn't declare. Names must pass
+/// [`identifiers::check_name`] and not be the name of a parameter in
+/// `material`.
+pub fn collect_variables(
+    graph: &GraphDescription,
+    declarations: &GraphDeclarations,
+    metadata_provider: &ShaderMetadataProvider,
+    material: &MaterialLayout,
+    entry_points: &EntryPointNames,
+    errors: &mut Vec<TypeError>,
+) -> BTreeMap<String, LocalVariable> {
+    let mut variables: BTreeMap<String, LocalVariable> = BTreeMap::new();
+    let mut declared = BTreeSet::new();
+
+    for declaration in &declarations.variables {
+        let name = declaration.name.trim();
+        let first_declaration = declared.insert(name);
+        let mut invalid = |reason: String| {
+            errors.push(TypeError::InvalidVariableDeclaration {
+                name: name.to_string(),
+                reason,
+            });
+        };
+
+        if let Err(reason) = identifiers::check_name(name, entry_points) {
+            invalid(format!("invalid variable name: {}", reason));
+            continue;
+        }
+        if material.parameter_type(name).is_some() {
+            invalid(format!("{} is already declared as a parameter", name));
+            continue;
+        }
+        if !first_declaration {
+            invalid(format!("{} is declared more than once", name));
+            continue;
+        }
+
+        let var_type = WgslType::parse(&declaration.var_type);
+        if var_type.component_count().is_none() {
+            invalid(format!("variables of type {} are not supported", declaration.var_type.trim()));
+            continue;
+        }
+
+        if let Some(initial_value) = &declaration.initial_value {
+            if let Err(reason) = literals::format_literal(initial_value, &var_type) {
+                invalid(format!("invalid initial value: {}", reason));
+                continue;
+            }
+        }
+
+        variables.insert(name.to_string(), LocalVariable {
+            name: name.to_string(),
+            var_type,
+            initial_value: declaration.initial_value.clone(),
+        });
+    }
+
+    let mut nodes: Vec<_> = graph.nodes.values().collect();
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    for node in nodes {
+        let Some(name) = variable_name(node, metadata_provider) else {
+            continue;
+        };
+
+        // Invalid declarations are reported on their own
+        if !declared.contains(name) {
+            errors.push(TypeError::InvalidVariable {
+                node_id: node.id.clone(),
+                reason: format!("'{}' is not a declared variable of the graph", name),
+            });
+        }
+    }
+
+    variables
+}
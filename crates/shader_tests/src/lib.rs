@@ -4,7 +4,15 @@
 
 #[cfg(test)]
 mod tests {
-    use psgc::{ShaderMetadataProvider, compile_fragment_shader};
+    use psgc::{
+        ShaderMetadataProvider, compile_fragment_shader, compile_vertex_shader, compile_module,
+        compile_compute_shader, compile_shader_with_policy, ConversionPolicy, CompiledShader,
+        ShaderStage, ResourceReflection, SamplerBindingType, CompileFailure, TextureDimension, TextureSampleType,
+        DiagnosticCode, Severity, CompileOptions, OptimizationLevel, compile_with_options,
+        CompileTarget, SourceMap, SpanKind, EntryPointNames, identifiers,
+        GraphDeclarations, compile_with_declarations,
+        UniformBufferReflection, UniformFieldReflection, ConversionKind, WgslType,
+    };
     use graphy::{
         NodeMetadataProvider, GraphDescription, NodeInstance, Connection,
         Pin, PinInstance, DataType, Position, ConnectionType, PropertyValue, PinType,
@@ -18,6 +26,77 @@ mod tests {
             .try_init();
     }
 
+    /// Create an entry point node with its `Body` execution output
+    fn entry_node(id: &str, node_type: &str) -> NodeInstance {
+        let mut node = NodeInstance::new(id, node_type, Position { x: 0.0, y: 0.0 });
+        let pin_id = format!("{}_Body", id);
+        node.outputs.push(PinInstance::new(
+            &pin_id,
+            Pin::new(&pin_id, "Body", DataType::Execution, PinType::Output)
+        ));
+        node
+    }
+
+    /// Create a node with typed data pins whose ids are `<node id>_<pin name>`
+    fn data_node(
+        id: &str,
+        node_type: &str,
+        inputs: &[(&str, &str)],
+        outputs: &[(&str, &str)],
+    ) -> NodeInstance {
+        let mut node = NodeInstance::new(id, node_type, Position { x: 0.0, y: 0.0 });
+        for (name, type_name) in inputs {
+            let pin_id = format!("{}_{}", id, name);
+            node.inputs.push(PinInstance::new(
+                &pin_id,
+                Pin::new(&pin_id, *name, DataType::Typed(psgc::TypeInfo::new(*type_name)), PinType::Input)
+            ));
+        }
+        for (name, type_name) in outputs {
+            let pin_id = format!("{}_{}", id, name);
+            node.outputs.push(PinInstance::new(
+                &pin_id,
+                Pin::new(&pin_id, *name, DataType::Typed(psgc::TypeInfo::new(*type_name)), PinType::Output)
+            ));
+        }
+        node
+    }
+
+    /// Connect `<source>_<source pin>` to `<target>_<target pin>`
+    fn connect(graph: &mut GraphDescription, source: (&str, &str), target: (&str, &str)) {
+        graph.add_connection(Connection::new(
+            source.0, &format!("{}_{}", source.0, source.1),
+            target.0, &format!("{}_{}", target.0, target.1),
+            ConnectionType::Data
+        ));
+    }
+
+    /// Add an `Exec` input and the given execution outputs to a node
+    fn with_exec_pins(mut node: NodeInstance, exec_outputs: &[&str]) -> NodeInstance {
+        let pin_id = format!("{}_Exec", node.id);
+        node.inputs.push(PinInstance::new(
+            &pin_id,
+            Pin::new(&pin_id, "Exec", DataType::Execution, PinType::Input)
+        ));
+        for name in exec_outputs {
+            let pin_id = format!("{}_{}", node.id, name);
+            node.outputs.push(PinInstance::new(
+                &pin_id,
+                Pin::new(&pin_id, *name, DataType::Execution, PinType::Output)
+            ));
+        }
+        node
+    }
+
+    /// Connect execution output `<source>_<source pin>` to `<target>_Exec`
+    fn connect_exec(graph: &mut GraphDescription, source: (&str, &str), target: &str) {
+        graph.add_connection(Connection::new(
+            source.0, &format!("{}_{}", source.0, source.1),
+            target, &format!("{}_Exec", target),
+            ConnectionType::Execution
+        ));
+    }
+
     /// Test: Load all shader nodes from wgsl_std
     #[test]
     fn test_load_shader_nodes() {
@@ -53,7 +132,7 @@ mod tests {
     /// Test: Compile a simple math shader
     /// 
     /// Graph structure:
-    /// frag_uv → multiply(uv.x, 2.0) → sin(x) → rgba(r,r,r,1) → fragment_output
+    /// frag_uv → vec2_split.x → multiply(x, 6.28) → sin(x) → rgba(r,0,0,1) → fragment_output
     #[test]
     fn test_compile_math_shader() {
         init_logging();
@@ -62,7 +141,7 @@ mod tests {
 
         let mut graph = GraphDescription::new("math_shader");
 
-        // Node 1: fragment_output (entry point)
+        // Node 1: fragment_main (entry point)
         let mut output = NodeInstance::new(
             "output_1",
             "fragment_main",
@@ -146,23 +225,60 @@ mod tests {
         let mut frag_uv = NodeInstance::new(
             "uv_1",
             "frag_uv",
-            Position { x: 50.0, y: 200.0 }
+            Position { x: -100.0, y: 200.0 }
         );
         frag_uv.outputs.push(PinInstance::new(
             "uv_1_result",
             Pin::new("uv_1_result", "result", DataType::Typed(psgc::TypeInfo::new("vec2<f32>")), PinType::Output)
         ));
 
+        // Node 6: vec2_split
+        let mut split = NodeInstance::new(
+            "split_1",
+            "vec2_split",
+            Position { x: 50.0, y: 200.0 }
+        );
+        split.inputs.push(PinInstance::new(
+            "split_1_v",
+            Pin::new("split_1_v", "v", DataType::Typed(psgc::TypeInfo::new("vec2<f32>")), PinType::Input)
+        ));
+        split.outputs.push(PinInstance::new(
+            "split_1_x",
+            Pin::new("split_1_x", "x", DataType::Typed(psgc::TypeInfo::new("f32")), PinType::Output)
+        ));
+        split.outputs.push(PinInstance::new(
+            "split_1_y",
+            Pin::new("split_1_y", "y", DataType::Typed(psgc::TypeInfo::new("f32")), PinType::Output)
+        ));
+
+        // Node 7: fragment_output
+        let mut frag_out = NodeInstance::new(
+            "frag_out_1",
+            "fragment_output",
+            Position { x: 850.0, y: 200.0 }
+        );
+        frag_out.inputs.push(PinInstance::new(
+            "frag_out_1_color",
+            Pin::new("frag_out_1_color", "color", DataType::Typed(psgc::TypeInfo::new("vec4<f32>")), PinType::Input)
+        ));
+
         // Add all nodes
         graph.add_node(output);
         graph.add_node(rgba);
         graph.add_node(sin_node);
         graph.add_node(multiply);
         graph.add_node(frag_uv);
+        graph.add_node(split);
+        graph.add_node(frag_out);
 
-        // Data connections: frag_uv.x → multiply → sin → rgba.r → output
+        // Data connections: frag_uv → split.x → multiply → sin → rgba.r → output
         graph.add_connection(Connection::new(
             "uv_1", "uv_1_result",
+            "split_1", "split_1_v",
+            ConnectionType::Data
+        ));
+        graph.add_connection(Connection::new(
+            "split_1", "split_1_x",
             "multiply_1", "multiply_1_a",
             ConnectionType::Data
         ));
@@ -176,6 +292,11 @@ mod tests {
             "rgba_1", "rgba_1_r",
             ConnectionType::Data
         ));
+        graph.add_connection(Connection::new(
+            "rgba_1", "rgba_1_result",
+            "frag_out_1", "frag_out_1_color",
+            ConnectionType::Data
+        ));
 
         println!("Graph created with {} nodes and {} connections\n", 
             graph.nodes.len(), graph.connections.len());
@@ -183,7 +304,8 @@ mod tests {
         // Compile the shader
         println!("=== Compiling Shader ===\n");
         match compile_fragment_shader(&graph) {
-            Ok(wgsl_code) => {
+            Ok(compiled) => {
+                let wgsl_code = compiled.wgsl;
                 println!("✓ === Compilation Successful! ===");
                 println!("Generated {} bytes of WGSL code\n", wgsl_code.len());
                 
@@ -200,8 +322,12 @@ mod tests {
                 // Basic validation
                 assert!(wgsl_code.contains("@fragment") || wgsl_code.contains("fragment"), 
                     "Should have fragment shader marker");
-                // Note: Full data flow code generation not yet implemented
-                // assert!(wgsl_code.contains("sin"), "Should use sin function");
+                assert!(!wgsl_code.contains("vec4<f32>(1.0, 0.0, 1.0, 1.0)"),
+                    "Should return the connected color instead of magenta");
+                assert!(wgsl_code.contains("sin(frag_uv.x * 6.28)"),
+                    "Should read the x component and inline the multiply and sin node sources");
+                assert!(wgsl_code.contains(", 0.0, 0.0, 1.0)"),
+                    "Integer constants should be formatted as f32 literals");
             }
             Err(e) => {
                 panic!("✗ Shader compilation failed: {}", e);
@@ -210,5 +336,1725 @@ mod tests {
 
         println!("✓ Test Passed! Math shader compiled successfully");
     }
-}
 
+    /// Test: Compile a vertex shader with a custom UV varying
+    ///
+    /// Graph structure:
+    /// vec4(0,0,0,1) → vertex_output.position, vertex_uv → vertex_output.uv
+    #[test]
+    fn test_compile_vertex_varyings() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("vertex_varyings");
+        graph.add_node(entry_node("vs_1", "vertex_main"));
+
+        let mut position = data_node(
+            "pos_1", "vec4",
+            &[("x", "f32"), ("y", "f32"), ("z", "f32"), ("w", "f32")],
+            &[("result", "vec4<f32>")],
+        );
+        position.properties.insert("pos_1_w".to_string(), PropertyValue::Number(1.0));
+        graph.add_node(position);
+        graph.add_node(data_node("uv_1", "vertex_uv", &[], &[("result", "vec2<f32>")]));
+        graph.add_node(data_node(
+            "vs_out_1", "vertex_output",
+            &[("position", "vec4<f32>"), ("uv", "vec2<f32>")],
+            &[],
+        ));
+
+        connect(&mut graph, ("pos_1", "result"), ("vs_out_1", "position"));
+        connect(&mut graph, ("uv_1", "result"), ("vs_out_1", "uv"));
+
+        let wgsl_code = compile_vertex_shader(&graph)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+
+        assert!(wgsl_code.contains("struct VertexOutput"), "Should declare VertexOutput");
+        assert!(wgsl_code.contains("@location(0) uv: vec2<f32>"), "Should declare the uv varying");
+        assert!(wgsl_code.contains("@location(2) vertex_uv: vec2<f32>"), "Should declare the uv attribute");
+        assert!(wgsl_code.contains("output.uv = vertex_uv;"), "Should write the uv varying");
+        assert!(!wgsl_code.contains("normal:"), "Unconnected varyings should be omitted");
+    }
+
+    /// Test: Stages compiled separately agree on the location of each varying
+    ///
+    /// Graph structure:
+    /// vertex: vertex_uv → vertex_output.uv, vertex_normal → vertex_output.normal
+    /// fragment: frag_normal → fragment_output (reads only the normal)
+    #[test]
+    fn test_separate_stage_locations() {
+        init_logging();
+
+        let mut vertex = GraphDescription::new("vertex_stage");
+        vertex.add_node(entry_node("vs_1", "vertex_main"));
+        let mut position = data_node("pos_1", "vec4", &[("x", "f32"), ("y", "f32"), ("z", "f32"), ("w", "f32")], &[("result", "vec4<f32>")]);
+        position.properties.insert("pos_1_w".to_string(), PropertyValue::Number(1.0));
+        vertex.add_node(position);
+        vertex.add_node(data_node("uv_1", "vertex_uv", &[], &[("result", "vec2<f32>")]));
+        vertex.add_node(data_node("normal_1", "vertex_normal", &[], &[("result", "vec3<f32>")]));
+        vertex.add_node(data_node(
+            "vs_out_1", "vertex_output",
+            &[("position", "vec4<f32>"), ("uv", "vec2<f32>"), ("normal", "vec3<f32>")],
+            &[],
+        ));
+        connect(&mut vertex, ("pos_1", "result"), ("vs_out_1", "position"));
+        connect(&mut vertex, ("uv_1", "result"), ("vs_out_1", "uv"));
+        connect(&mut vertex, ("normal_1", "result"), ("vs_out_1", "normal"));
+
+        let mut fragment = GraphDescription::new("fragment_stage");
+        fragment.add_node(entry_node("fs_1", "fragment_main"));
+        fragment.add_node(data_node("normal_1", "frag_normal", &[], &[("result", "vec3<f32>")]));
+        fragment.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut fragment, ("normal_1", "result"), ("fs_out_1", "color"));
+
+        let vertex_code = compile_vertex_shader(&vertex)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        let fragment_code = compile_fragment_shader(&fragment)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}\n{}", vertex_code, fragment_code);
+
+        assert!(vertex_code.contains("    @location(0) uv: vec2<f32>,\n    @location(1) normal: vec3<f32>,\n"),
+            "Varyings should have fixed locations");
+        assert!(fragment_code.contains("    @location(1) frag_normal: vec3<f32>,\n"),
+            "The fragment stage should read the normal from the vertex stage's location");
+    }
+
+    /// Build a vertex + fragment graph passing vertex colors to the fragment stage
+    fn vertex_color_graph(connect_color_varying: bool) -> GraphDescription {
+        let mut graph = GraphDescription::new("vertex_color");
+        graph.add_node(entry_node("vs_1", "vertex_main"));
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+
+        let mut position = data_node(
+            "pos_1", "vec4",
+            &[("x", "f32"), ("y", "f32"), ("z", "f32"), ("w", "f32")],
+            &[("result", "vec4<f32>")],
+        );
+        position.properties.insert("pos_1_w".to_string(), PropertyValue::Number(1.0));
+        graph.add_node(position);
+        graph.add_node(data_node("vcolor_1", "vertex_color", &[], &[("result", "vec4<f32>")]));
+        graph.add_node(data_node(
+            "vs_out_1", "vertex_output",
+            &[("position", "vec4<f32>"), ("color", "vec4<f32>")],
+            &[],
+        ));
+        graph.add_node(data_node("fcolor_1", "frag_color", &[], &[("result", "vec4<f32>")]));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+
+        connect(&mut graph, ("pos_1", "result"), ("vs_out_1", "position"));
+        if connect_color_varying {
+            connect(&mut graph, ("vcolor_1", "result"), ("vs_out_1", "color"));
+        }
+        connect(&mut graph, ("fcolor_1", "result"), ("fs_out_1", "color"));
+
+        graph
+    }
+
+    /// Test: Compile vertex and fragment stages into one module
+    #[test]
+    fn test_compile_module() {
+        init_logging();
+
+        let wgsl_code = compile_module(&vertex_color_graph(true))
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+
+        assert_eq!(wgsl_code.matches("struct VertexOutput").count(), 1,
+            "Should declare VertexOutput once");
+        assert!(wgsl_code.contains("@vertex"), "Should have vertex entry point");
+        assert!(wgsl_code.contains("@fragment"), "Should have fragment entry point");
+        assert!(wgsl_code.contains("input: VertexOutput"), "Fragment should read VertexOutput");
+        assert!(wgsl_code.contains("let frag_color = input.color;"), "Should bind frag_color");
+    }
+
+    /// Test: Fragment inputs without a matching vertex output are rejected
+    #[test]
+    fn test_compile_module_missing_varying() {
+        init_logging();
+
+        let error = compile_module(&vertex_color_graph(false))
+            .expect_err("Module without a color varying should not compile");
+
+        assert!(error.to_string().contains("frag_color"), "Error should name the fragment input");
+    }
+
+    /// Test: Compile a compute shader with a custom workgroup size
+    #[test]
+    fn test_compile_compute_shader() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("compute_shader");
+        let mut entry = entry_node("cs_1", "compute_main");
+        entry.properties.insert("workgroup_size_x".to_string(), PropertyValue::Number(64.0));
+        entry.properties.insert("workgroup_size_y".to_string(), PropertyValue::Number(1.0));
+        graph.add_node(entry);
+        graph.add_node(data_node("gid_1", "global_invocation_id", &[], &[("result", "vec3<u32>")]));
+
+        let compiled = compile_compute_shader(&graph)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        let wgsl_code = &compiled.wgsl;
+        println!("{}", wgsl_code);
+
+        let entry_point = compiled.reflection.entry_point(ShaderStage::Compute)
+            .expect("Reflection should list the compute entry point");
+        assert_eq!(entry_point.workgroup_size, Some([64, 1, 1]),
+            "Reflection should report the workgroup size");
+
+        assert!(wgsl_code.contains("@compute @workgroup_size(64, 1, 1)"),
+            "Should use the configured workgroup size");
+        assert!(wgsl_code.contains("@builtin(global_invocation_id) global_invocation_id: vec3<u32>"),
+            "Should declare the used builtin");
+        assert!(!wgsl_code.contains("workgroup_id:"), "Unused builtins should be omitted");
+
+        // 64 * 8 invocations exceeds the WebGPU default limit
+        let mut oversized = graph.clone();
+        if let Some(entry) = oversized.nodes.get_mut("cs_1") {
+            entry.properties.insert("workgroup_size_y".to_string(), PropertyValue::Number(8.0));
+        }
+        assert!(compile_compute_shader(&oversized).is_err(), "Oversized workgroups should be rejected");
+    }
+
+    /// Test: Non-trivial nodes are emitted once as helper functions
+    ///
+    /// Graph structure:
+    /// frag_normal → desaturate ×2 → dot → rgba.r → fragment_output
+    #[test]
+    fn test_helper_functions() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("helper_functions");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(data_node("normal_1", "frag_normal", &[], &[("result", "vec3<f32>")]));
+        for id in ["desat_1", "desat_2"] {
+            graph.add_node(data_node(
+                id, "desaturate",
+                &[("color", "vec3<f32>"), ("amount", "f32")],
+                &[("result", "vec3<f32>")],
+            ));
+            connect(&mut graph, ("normal_1", "result"), (id, "color"));
+        }
+        graph.add_node(data_node(
+            "dot_1", "dot",
+            &[("a", "vec3<f32>"), ("b", "vec3<f32>")],
+            &[("result", "f32")],
+        ));
+        graph.add_node(data_node(
+            "rgba_1", "rgba",
+            &[("r", "f32"), ("g", "f32"), ("b", "f32"), ("a", "f32")],
+            &[("result", "vec4<f32>")],
+        ));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+
+        connect(&mut graph, ("desat_1", "result"), ("dot_1", "a"));
+        connect(&mut graph, ("desat_2", "result"), ("dot_1", "b"));
+        connect(&mut graph, ("dot_1", "result"), ("rgba_1", "r"));
+        connect(&mut graph, ("rgba_1", "result"), ("fs_out_1", "color"));
+
+        let wgsl_code = compile_fragment_shader(&graph)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+
+        assert_eq!(wgsl_code.matches("fn node_desaturate(color: vec3<f32>, amount: f32) -> vec3<f32>").count(), 1,
+            "Should emit the desaturate helper once");
+        assert_eq!(wgsl_code.matches("node_desaturate(frag_normal").count(), 2,
+            "Both desaturate nodes should call the helper");
+        assert_eq!(wgsl_code.matches("mix(").count(), 1,
+            "The desaturate body should only appear in the helper");
+        assert!(!wgsl_code.contains("fn node_dot"), "Trivial nodes should be inlined");
+    }
+
+    /// Test: Unconnected inputs use typed defaults, resources must be connected
+    #[test]
+    fn test_default_values() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("default_values");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+
+        let wgsl_code = compile_fragment_shader(&graph)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        assert!(wgsl_code.contains("return vec4<f32>(0.0, 0.0, 0.0, 1.0);"),
+            "Unconnected color should use the fragment_output default");
+
+        // A texture sample without texture and sampler connections can't be compiled
+        graph.add_node(data_node(
+            "sample_1", "sample_texture",
+            &[("texture", "texture_2d<f32>"), ("sampler", "sampler"), ("uv", "vec2<f32>")],
+            &[("result", "vec4<f32>")],
+        ));
+        connect(&mut graph, ("sample_1", "result"), ("fs_out_1", "color"));
+
+        let error = compile_fragment_shader(&graph)
+            .expect_err("Unconnected texture input should be rejected");
+        assert!(error.to_string().contains("sample_1.texture"), "Error should name the input");
+    }
+
+    /// Test: Constants are formatted as literals of the destination type
+    #[test]
+    fn test_constant_literals() {
+        init_logging();
+
+        let compile_with_color = |color: PropertyValue| {
+            let mut graph = GraphDescription::new("constant_literals");
+            graph.add_node(entry_node("fs_1", "fragment_main"));
+            let mut output = data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]);
+            output.properties.insert("fs_out_1_color".to_string(), color);
+            graph.add_node(output);
+            compile_fragment_shader(&graph)
+        };
+
+        let splat = compile_with_color(PropertyValue::Number(1.0))
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        assert!(splat.contains("return vec4<f32>(1.0);"), "Numbers should splat across vectors");
+
+        let hex = compile_with_color(PropertyValue::String("#ff0000".to_string()))
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        assert!(hex.contains("return vec4<f32>(1.0, 0.0, 0.0, 1.0);"), "Hex colors should become vec4 literals");
+
+        assert!(compile_with_color(PropertyValue::String("red".to_string())).is_err(),
+            "Values that aren't numbers should be rejected");
+        assert!(compile_with_color(PropertyValue::String("1, 2".to_string())).is_err(),
+            "Component counts must match the vector size");
+
+        // `-2147483648i` negates an unrepresentable literal
+        let compile_with_counter = |initial_value: f64| {
+            let mut graph = GraphDescription::new("integer_literals");
+            graph.add_node(entry_node("fs_1", "fragment_main"));
+            graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+            let declarations = GraphDeclarations::new()
+                .with_variable("counter", "i32", Some(&initial_value.to_string()));
+            let mut options = CompileOptions::for_stages(&[ShaderStage::Fragment]);
+            options.validate = true;
+            compile_with_declarations(&graph, &declarations, &options)
+        };
+        let minimum = compile_with_counter(i32::MIN as f64)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        assert!(minimum.contains("    var counter: i32 = i32(-2147483647 - 1);\n"),
+            "i32::MIN should be built from representable literals");
+        let negative = compile_with_counter(-5.0)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        assert!(negative.contains("    var counter: i32 = -5i;\n"), "Other values should stay plain literals");
+        assert!(compile_with_counter(i32::MIN as f64 - 1.0).is_err(), "Values below i32::MIN should be rejected");
+    }
+
+    /// Test: Mismatched wires are reported before code generation
+    ///
+    /// Graph structure:
+    /// frag_uv (vec2<f32>) → vec3.x (f32)
+    #[test]
+    fn test_type_mismatch() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("type_mismatch");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(data_node("uv_1", "frag_uv", &[], &[("result", "vec2<f32>")]));
+        graph.add_node(data_node(
+            "vec3_1", "vec3",
+            &[("x", "f32"), ("y", "f32"), ("z", "f32")],
+            &[("result", "vec3<f32>")],
+        ));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut graph, ("uv_1", "result"), ("vec3_1", "x"));
+
+        let error = compile_fragment_shader(&graph)
+            .expect_err("vec2<f32> into f32 should not compile");
+        let message = error.to_string();
+        println!("{}", message);
+
+        assert!(message.contains("uv_1"), "Error should name the source node");
+        assert!(message.contains("vec3_1.x"), "Error should name the target pin");
+        assert!(message.contains("vec2<f32>"), "Error should name the found type");
+    }
+
+    /// Test: Every error is reported with the node and pin it's about
+    ///
+    /// Graph structure:
+    /// frag_uv (vec2<f32>) → vec3.x (f32), frag_uv (vec2<f32>) → vec4.x (f32)
+    /// frag_color (vec4<f32>) → desaturate.color (vec3<f32>) → fragment_output
+    #[test]
+    fn test_diagnostics() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("diagnostics");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(data_node("uv_1", "frag_uv", &[], &[("result", "vec2<f32>")]));
+        graph.add_node(data_node("color_1", "frag_color", &[], &[("result", "vec4<f32>")]));
+        graph.add_node(data_node(
+            "desaturate_1", "desaturate",
+            &[("color", "vec3<f32>"), ("amount", "f32")],
+            &[("result", "vec3<f32>")],
+        ));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut graph, ("color_1", "result"), ("desaturate_1", "color"));
+        connect(&mut graph, ("desaturate_1", "result"), ("fs_out_1", "color"));
+
+        let compiled = compile_fragment_shader(&graph)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        assert_eq!(compiled.diagnostics.len(), 1, "Truncation should be reported once");
+        let warning = &compiled.diagnostics[0];
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(warning.code, DiagnosticCode::ImplicitConversion);
+        assert_eq!(warning.node_id.as_deref(), Some("desaturate_1"));
+        assert_eq!(warning.pin_id.as_deref(), Some("desaturate_1_color"));
+        assert_eq!(warning.related_nodes, vec!["color_1".to_string()]);
+
+        graph.add_node(data_node(
+            "vec3_1", "vec3",
+            &[("x", "f32"), ("y", "f32"), ("z", "f32")],
+            &[("result", "vec3<f32>")],
+        ));
+        graph.add_node(data_node(
+            "vec4_1", "vec4",
+            &[("x", "f32"), ("y", "f32"), ("z", "f32"), ("w", "f32")],
+            &[("result", "vec4<f32>")],
+        ));
+        connect(&mut graph, ("uv_1", "result"), ("vec3_1", "x"));
+        connect(&mut graph, ("uv_1", "result"), ("vec4_1", "x"));
+
+        let failure = compile_fragment_shader(&graph)
+            .expect_err("vec2<f32> into f32 should not compile");
+        println!("{}", failure);
+
+        let errors: Vec<_> = failure.errors().collect();
+        assert_eq!(errors.len(), 2, "Both mismatched wires should be reported");
+        assert!(errors.iter().all(|error| error.code == DiagnosticCode::TypeMismatch));
+        let vec3_error = errors.iter()
+            .find(|error| error.node_id.as_deref() == Some("vec3_1"))
+            .expect("vec3_1 should have an error");
+        assert_eq!(vec3_error.pin_id.as_deref(), Some("vec3_1_x"), "Error should point at the input pin");
+        assert_eq!(vec3_error.related_nodes, vec!["uv_1".to_string()], "Error should link the source node");
+        assert_eq!(failure.warnings().count(), 1, "Warnings should survive a failed compilation");
+        assert_eq!(failure.diagnostics.last().map(|d| d.severity), Some(Severity::Warning),
+            "Errors should be listed before warnings");
+
+        let json = serde_json::to_string(&failure).expect("Diagnostics should serialize");
+        assert!(json.contains("\"code\":\"type_mismatch\""), "Codes should serialize in snake_case");
+    }
+
+    /// Test: Compatible types are converted implicitly unless the policy denies it
+    ///
+    /// Graph structure:
+    /// rgb (vec3<f32>) → fragment_output.color (vec4<f32>)
+    /// vertex_position (vec3<f32>) → vertex_output.position / vertex_output.color
+    /// frag_color (vec4<f32>) → vec2_split.v (vec2<f32>)
+    #[test]
+    fn test_implicit_conversions() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("implicit_conversions");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(data_node(
+            "rgb_1", "rgb",
+            &[("r", "f32"), ("g", "f32"), ("b", "f32")],
+            &[("result", "vec3<f32>")],
+        ));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut graph, ("rgb_1", "result"), ("fs_out_1", "color"));
+
+        let wgsl_code = compile_fragment_shader(&graph)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+        assert!(wgsl_code.contains("return vec4<f32>(vec3(0.0, 0.0, 0.0), 1.0);"),
+            "vec3 colors should get an opaque alpha");
+
+        let error = compile_shader_with_policy(&graph, &ConversionPolicy::strict())
+            .expect_err("Strict policy should reject implicit conversions");
+        assert!(error.to_string().contains("fs_out_1.color"), "Error should name the target pin");
+
+        // Truncation only drops the alpha, appending one only fills a color
+        let vec2 = WgslType::parse("vec2<f32>");
+        let vec3 = WgslType::parse("vec3<f32>");
+        let vec4 = WgslType::parse("vec4<f32>");
+        assert_eq!(ConversionKind::between(&vec4, &vec3, false), Some(ConversionKind::Truncate));
+        assert_eq!(ConversionKind::between(&vec4, &vec2, false), None);
+        assert_eq!(ConversionKind::between(&vec3, &vec2, false), None);
+        assert_eq!(ConversionKind::between(&WgslType::parse("vec4<i32>"), &WgslType::parse("vec3<i32>"), false),
+            Some(ConversionKind::Truncate));
+        assert_eq!(ConversionKind::between(&vec3, &vec4, true), Some(ConversionKind::AppendAlpha));
+        assert_eq!(ConversionKind::between(&vec3, &vec4, false), None);
+        assert_eq!(ConversionKind::between(&vec2, &vec4, true), None);
+
+        // vertex_output.position isn't a color, vertex_output.color is
+        let vertex_graph = |target_pin: &str| {
+            let mut vertex = GraphDescription::new("vertex_conversions");
+            vertex.add_node(entry_node("vs_1", "vertex_main"));
+            vertex.add_node(data_node("position_1", "vertex_position", &[], &[("result", "vec3<f32>")]));
+            vertex.add_node(data_node(
+                "vs_out_1", "vertex_output",
+                &[("position", "vec4<f32>"), ("color", "vec4<f32>")],
+                &[],
+            ));
+            connect(&mut vertex, ("position_1", "result"), ("vs_out_1", target_pin));
+            compile_vertex_shader(&vertex)
+        };
+        let error = vertex_graph("position").expect_err("A vec3 position shouldn't get an alpha");
+        assert!(error.errors().any(|error| error.code == DiagnosticCode::TypeMismatch
+            && error.pin_id.as_deref() == Some("vs_out_1_position")));
+        let wgsl_code = vertex_graph("color")
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        assert!(wgsl_code.contains("output.color = vec4<f32>(vertex_position, 1.0);"),
+            "vec3 vertex colors should get an opaque alpha");
+
+        // Only vec4 → vec3 truncates
+        let mut truncated = GraphDescription::new("truncation");
+        truncated.add_node(entry_node("fs_1", "fragment_main"));
+        truncated.add_node(data_node("color_1", "frag_color", &[], &[("result", "vec4<f32>")]));
+        truncated.add_node(data_node("split_1", "vec2_split", &[("v", "vec2<f32>")], &[("x", "f32"), ("y", "f32")]));
+        truncated.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut truncated, ("color_1", "result"), ("split_1", "v"));
+        connect(&mut truncated, ("split_1", "x"), ("fs_out_1", "color"));
+        let error = compile_fragment_shader(&truncated).expect_err("vec4 into vec2 should not truncate");
+        assert!(error.errors().any(|error| error.code == DiagnosticCode::TypeMismatch
+            && error.node_id.as_deref() == Some("split_1")));
+    }
+
+    /// Test: Generic math nodes resolve their type from connected inputs
+    ///
+    /// Graph structure:
+    /// frag_normal → multiply.a, frag_normal → length → multiply.b
+    /// multiply → normalize → fragment_output
+    #[test]
+    fn test_generic_nodes() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("generic_nodes");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(data_node("normal_1", "frag_normal", &[], &[("result", "vec3<f32>")]));
+        graph.add_node(data_node("length_1", "length", &[("v", "vec3<f32>")], &[("result", "f32")]));
+        graph.add_node(data_node(
+            "mul_1", "multiply",
+            &[("a", "vec3<f32>"), ("b", "f32")],
+            &[("result", "vec3<f32>")],
+        ));
+        graph.add_node(data_node("normalize_1", "normalize", &[("v", "vec3<f32>")], &[("result", "vec3<f32>")]));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+
+        connect(&mut graph, ("normal_1", "result"), ("length_1", "v"));
+        connect(&mut graph, ("normal_1", "result"), ("mul_1", "a"));
+        connect(&mut graph, ("length_1", "result"), ("mul_1", "b"));
+        connect(&mut graph, ("mul_1", "result"), ("normalize_1", "v"));
+        connect(&mut graph, ("normalize_1", "result"), ("fs_out_1", "color"));
+
+        let wgsl_code = compile_fragment_shader(&graph)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+
+        assert!(wgsl_code.contains("frag_normal * vec3<f32>(length(frag_normal))"),
+            "Scalar should be splatted to the vector type of multiply");
+        assert!(wgsl_code.contains("return vec4<f32>(normalize("),
+            "multiply should resolve to vec3<f32> and flow into normalize");
+
+        // Unconnected generic inputs take the first allowed type
+        let mut scalar = GraphDescription::new("generic_defaults");
+        scalar.add_node(entry_node("fs_1", "fragment_main"));
+        scalar.add_node(data_node(
+            "mul_1", "multiply",
+            &[("a", "f32"), ("b", "f32")],
+            &[("result", "f32")],
+        ));
+        scalar.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut scalar, ("mul_1", "result"), ("fs_out_1", "color"));
+
+        let wgsl_code = compile_fragment_shader(&scalar)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        assert!(wgsl_code.contains("return vec4<f32>(0.0 * 1.0);"),
+            "Unconnected multiply should resolve to f32 and be splatted into the color");
+    }
+
+    /// Test: Swizzle nodes read components selected by their mask property
+    ///
+    /// Graph structure:
+    /// frag_color → swizzle("bgr") → fragment_output
+    #[test]
+    fn test_swizzle() {
+        init_logging();
+
+        let compile_with_mask = |mask: &str| {
+            let mut graph = GraphDescription::new("swizzle");
+            graph.add_node(entry_node("fs_1", "fragment_main"));
+            graph.add_node(data_node("color_1", "frag_color", &[], &[("result", "vec4<f32>")]));
+            let mut swizzle = data_node("swizzle_1", "swizzle", &[("v", "vec4<f32>")], &[("result", "vec3<f32>")]);
+            swizzle.properties.insert("mask".to_string(), PropertyValue::String(mask.to_string()));
+            graph.add_node(swizzle);
+            graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+            connect(&mut graph, ("color_1", "result"), ("swizzle_1", "v"));
+            connect(&mut graph, ("swizzle_1", "result"), ("fs_out_1", "color"));
+            compile_fragment_shader(&graph)
+        };
+
+        let wgsl_code = compile_with_mask("bgr")
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+        assert!(wgsl_code.contains("return vec4<f32>(frag_color.bgr, 1.0);"),
+            "Swizzled vec3 should be read with the mask and get an opaque alpha");
+
+        let error = compile_with_mask("xq").expect_err("Invalid masks should be rejected");
+        assert!(error.to_string().contains("swizzle_1"), "Error should name the swizzle node");
+    }
+
+    /// Test: Branches become if/else blocks and discard is fragment-only
+    ///
+    /// Graph structure:
+    /// fragment_main → branch(frag_color.a < 0.5) → Then: discard
+    #[test]
+    fn test_branch_discard() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("alpha_clip");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(data_node("color_1", "frag_color", &[], &[("result", "vec4<f32>")]));
+        graph.add_node(data_node(
+            "split_1", "color_split",
+            &[("color", "vec4<f32>")],
+            &[("r", "f32"), ("g", "f32"), ("b", "f32"), ("a", "f32")],
+        ));
+        let mut less_than = data_node("less_1", "less_than", &[("a", "f32"), ("b", "f32")], &[("result", "bool")]);
+        less_than.properties.insert("less_1_b".to_string(), PropertyValue::Number(0.5));
+        graph.add_node(less_than);
+        graph.add_node(with_exec_pins(
+            data_node("branch_1", "branch", &[("condition", "bool")], &[]),
+            &["Then", "Else"],
+        ));
+        graph.add_node(with_exec_pins(data_node("discard_1", "discard", &[], &[]), &[]));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+
+        connect(&mut graph, ("color_1", "result"), ("split_1", "color"));
+        connect(&mut graph, ("split_1", "a"), ("less_1", "a"));
+        connect(&mut graph, ("less_1", "result"), ("branch_1", "condition"));
+        connect(&mut graph, ("color_1", "result"), ("fs_out_1", "color"));
+        connect_exec(&mut graph, ("fs_1", "Body"), "branch_1");
+        connect_exec(&mut graph, ("branch_1", "Then"), "discard_1");
+
+        let wgsl_code = compile_fragment_shader(&graph)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+        assert!(wgsl_code.contains("    if frag_color.a < 0.5 {\n        discard;\n    }\n"),
+            "Branch should emit an indented if block around the discard");
+        assert!(!wgsl_code.contains("} else {"), "Unconnected Else should not emit an else block");
+
+        // discard is only valid in fragment shaders
+        let mut vertex = GraphDescription::new("vertex_discard");
+        vertex.add_node(entry_node("vs_1", "vertex_main"));
+        vertex.add_node(with_exec_pins(data_node("discard_1", "discard", &[], &[]), &[]));
+        vertex.add_node(data_node("vs_out_1", "vertex_output", &[("position", "vec4<f32>")], &[]));
+        connect_exec(&mut vertex, ("vs_1", "Body"), "discard_1");
+
+        let error = compile_vertex_shader(&vertex).expect_err("discard should be rejected in vertex shaders");
+        assert!(error.to_string().contains("discard_1"), "Error should name the discard node");
+
+        // A node both arms lead to is generated once in each arm, and not again
+        let mut rejoined = GraphDescription::new("rejoined_branch");
+        rejoined.add_node(entry_node("fs_1", "fragment_main"));
+        let mut greater_than = data_node("greater_1", "greater_than", &[("a", "f32"), ("b", "f32")], &[("result", "bool")]);
+        greater_than.properties.insert("greater_1_b".to_string(), PropertyValue::Number(0.5));
+        rejoined.add_node(greater_than);
+        rejoined.add_node(with_exec_pins(
+            data_node("branch_1", "branch", &[("condition", "bool")], &[]),
+            &["Then", "Else"],
+        ));
+        rejoined.add_node(with_exec_pins(data_node("discard_1", "discard", &[], &[]), &[]));
+        rejoined.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        let mut for_loop = with_exec_pins(
+            data_node("loop_1", "for_loop", &[("count", "u32")], &[("index", "u32")]),
+            &["Body", "Completed"],
+        );
+        for_loop.properties.insert("loop_1_count".to_string(), PropertyValue::Number(2.0));
+        rejoined.add_node(for_loop);
+        connect(&mut rejoined, ("greater_1", "result"), ("branch_1", "condition"));
+        connect_exec(&mut rejoined, ("fs_1", "Body"), "branch_1");
+        connect_exec(&mut rejoined, ("branch_1", "Then"), "loop_1");
+        connect_exec(&mut rejoined, ("branch_1", "Else"), "loop_1");
+        connect_exec(&mut rejoined, ("loop_1", "Body"), "discard_1");
+
+        let mut options = CompileOptions::for_stages(&[ShaderStage::Fragment]);
+        options.validate = true;
+        let wgsl_code = compile_with_options(&rejoined, &options)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+        let (then_arm, else_arm) = wgsl_code
+            .split_once("    } else {\n")
+            .expect("Both arms should be emitted");
+        let (else_arm, after_branch) = else_arm
+            .split_once("\n    }\n")
+            .expect("The else block should be closed");
+        assert_eq!(then_arm.matches("for (var ").count(), 1, "Then should run the loop once");
+        assert_eq!(else_arm.matches("for (var ").count(), 1, "Else should run the loop once");
+        assert_eq!(after_branch.matches("discard;").count(), 0,
+            "The loop should not run again after the branch");
+    }
+
+    /// Test: Loops are bounded and their index is scoped to the loop body
+    ///
+    /// Graph structure:
+    /// fragment_main → for_loop(4) → Body: discard
+    #[test]
+    fn test_for_loop() {
+        init_logging();
+
+        let loop_graph = |count: f64| {
+            let mut graph = GraphDescription::new("for_loop");
+            graph.add_node(entry_node("fs_1", "fragment_main"));
+            let mut for_loop = with_exec_pins(
+                data_node("loop_1", "for_loop", &[("count", "u32")], &[("index", "u32")]),
+                &["Body", "Completed"],
+            );
+            for_loop.properties.insert("loop_1_count".to_string(), PropertyValue::Number(count));
+            graph.add_node(for_loop);
+            graph.add_node(with_exec_pins(data_node("discard_1", "discard", &[], &[]), &[]));
+            graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+            connect_exec(&mut graph, ("fs_1", "Body"), "loop_1");
+            connect_exec(&mut graph, ("loop_1", "Body"), "discard_1");
+            graph
+        };
+
+        let wgsl_code = compile_fragment_shader(&loop_graph(4.0))
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+        assert!(wgsl_code.contains("    for (var "), "Should emit a for loop");
+        assert!(wgsl_code.contains(" < 4u; "), "Loop should be bounded by the constant count");
+        assert!(wgsl_code.contains("        discard;"), "Loop body should be indented");
+
+        assert!(compile_fragment_shader(&loop_graph(100_000.0)).is_err(),
+            "Counts above the iteration limit should be rejected");
+
+        // A broken node doesn't stop the nodes after it from being checked
+        let mut graph = loop_graph(100_000.0);
+        let mut second_loop = with_exec_pins(
+            data_node("loop_2", "for_loop", &[("count", "u32")], &[("index", "u32")]),
+            &["Body", "Completed"],
+        );
+        second_loop.properties.insert("loop_2_count".to_string(), PropertyValue::Number(200_000.0));
+        graph.add_node(second_loop);
+        connect_exec(&mut graph, ("fs_1", "Body"), "loop_2");
+
+        let failure = compile_fragment_shader(&graph)
+            .expect_err("Counts above the iteration limit should be rejected");
+        println!("{}", failure);
+        let errors: Vec<_> = failure.errors().collect();
+        assert_eq!(errors.len(), 2, "Both broken loops should be reported");
+        assert!(errors.iter().all(|error| error.code == DiagnosticCode::InvalidLoopCount));
+        assert_eq!(errors[0].pin_id.as_deref(), Some("loop_1_count"));
+        assert_eq!(errors[1].pin_id.as_deref(), Some("loop_2_count"));
+
+        // The loop index can't be read after the loop
+        let mut graph = loop_graph(4.0);
+        graph.add_node(data_node(
+            "rgba_1", "rgba",
+            &[("r", "f32"), ("g", "f32"), ("b", "f32"), ("a", "f32")],
+            &[("result", "vec4<f32>")],
+        ));
+        connect(&mut graph, ("loop_1", "index"), ("rgba_1", "r"));
+        connect(&mut graph, ("rgba_1", "result"), ("fs_out_1", "color"));
+
+        let error = compile_fragment_shader(&graph)
+            .expect_err("Loop index should be out of scope in the return statement");
+        assert!(error.to_string().contains("loop_1"), "Error should name the loop node");
+    }
+
+    /// Name the variable a `set_variable`/`get_variable` node accesses
+    fn accessing(mut node: NodeInstance, name: &str) -> NodeInstance {
+        node.properties.insert("variable".to_string(), PropertyValue::String(name.to_string()));
+        node
+    }
+
+    /// Compile a fragment shader with the given graph-level declarations
+    fn compile_fragment_declaring(
+        graph: &GraphDescription,
+        declarations: &GraphDeclarations,
+    ) -> Result<CompiledShader, CompileFailure> {
+        compile_with_declarations(graph, declarations, &CompileOptions::for_stages(&[ShaderStage::Fragment]))
+    }
+
+    /// Test: Local variables are declared with `var` and assigned in execution order
+    ///
+    /// Graph structure:
+    /// declarations: brightness: f32 = 0.0
+    /// fragment_main → for_loop(3) → Body: set_variable(brightness, brightness + 0.25)
+    /// get_variable(brightness) → fragment_output
+    #[test]
+    fn test_local_variables() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("local_variables");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        let mut for_loop = with_exec_pins(
+            data_node("loop_1", "for_loop", &[("count", "u32")], &[("index", "u32")]),
+            &["Body", "Completed"],
+        );
+        for_loop.properties.insert("loop_1_count".to_string(), PropertyValue::Number(3.0));
+        graph.add_node(for_loop);
+
+        graph.add_node(accessing(
+            with_exec_pins(data_node("set_1", "set_variable", &[("value", "f32")], &[]), &["Then"]),
+            "brightness",
+        ));
+        for id in ["get_1", "get_2"] {
+            graph.add_node(accessing(data_node(id, "get_variable", &[], &[("result", "f32")]), "brightness"));
+        }
+
+        let mut add = data_node("add_1", "add", &[("a", "f32"), ("b", "f32")], &[("result", "f32")]);
+        add.properties.insert("add_1_b".to_string(), PropertyValue::Number(0.25));
+        graph.add_node(add);
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+
+        connect(&mut graph, ("get_1", "result"), ("add_1", "a"));
+        connect(&mut graph, ("add_1", "result"), ("set_1", "value"));
+        connect(&mut graph, ("get_2", "result"), ("fs_out_1", "color"));
+        connect_exec(&mut graph, ("fs_1", "Body"), "loop_1");
+        connect_exec(&mut graph, ("loop_1", "Body"), "set_1");
+
+        let declarations = GraphDeclarations::new().with_variable("brightness", "f32", Some("0.0"));
+        let wgsl_code = compile_fragment_declaring(&graph, &declarations)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+        assert!(wgsl_code.contains("    var brightness: f32 = 0.0;\n"),
+            "Variable should be declared at the top of the entry function");
+        assert!(wgsl_code.contains("        brightness = (brightness + 0.25);\n"),
+            "Assignment should be emitted inside the loop body");
+        assert!(wgsl_code.contains("return vec4<f32>(brightness);"),
+            "Reads after the loop should see the variable");
+
+        // Node ID (if any) and message of each variable error
+        let variable_errors = |declarations: &GraphDeclarations| -> Vec<(Option<String>, String)> {
+            let error = compile_fragment_declaring(&graph, declarations)
+                .expect_err("Invalid variables should be rejected");
+            error
+                .errors()
+                .filter(|diagnostic| diagnostic.code == DiagnosticCode::InvalidVariable)
+                .map(|diagnostic| (diagnostic.node_id.clone(), diagnostic.message.clone()))
+                .collect()
+        };
+
+        let undeclared = variable_errors(&GraphDeclarations::new());
+        let nodes: Vec<_> = undeclared.iter().filter_map(|(node_id, _)| node_id.as_deref()).collect();
+        assert_eq!(nodes, ["get_1", "get_2", "set_1"],
+            "Each node accessing an undeclared variable should be reported");
+
+        let redeclared = variable_errors(&declarations.clone().with_variable("brightness", "vec3<f32>", None));
+        assert_eq!(redeclared.len(), 1, "Variables can only be declared once");
+        assert!(redeclared[0].0.is_none() && redeclared[0].1.contains("brightness"),
+            "Declaration errors should name the variable, not a node");
+
+        let unsupported = variable_errors(&GraphDeclarations::new().with_variable("brightness", "texture_2d<f32>", None));
+        assert_eq!(unsupported.len(), 1, "Nodes accessing an invalid declaration should not be reported again");
+        assert!(unsupported[0].1.contains("texture_2d<f32>"), "Error should name the unsupported type");
+
+        let invalid_value = variable_errors(&GraphDeclarations::new().with_variable("brightness", "f32", Some("bright")));
+        assert!(invalid_value.len() == 1 && invalid_value[0].1.contains("initial value"),
+            "Initial values should be checked against the type");
+    }
+
+    /// Test: Output doesn't depend on the order nodes and connections were added
+    ///
+    /// Graph structure:
+    /// declarations: second: f32, first: f32
+    /// fragment_main → set_variable(second, 2.0)
+    ///               → set_variable(first, 1.0)
+    /// get_variable(first) → fragment_output
+    #[test]
+    fn test_deterministic_output() {
+        init_logging();
+
+        let sibling_graph = |reversed: bool| {
+            let mut graph = GraphDescription::new("deterministic");
+            let mut nodes = vec![
+                entry_node("fs_1", "fragment_main"),
+                data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]),
+            ];
+            for (id, variable, value) in [("set_2", "second", 2.0), ("set_1", "first", 1.0)] {
+                let mut set = with_exec_pins(data_node(id, "set_variable", &[("value", "f32")], &[]), &["Then"]);
+                set.properties.insert(format!("{}_value", id), PropertyValue::Number(value));
+                nodes.push(accessing(set, variable));
+            }
+            nodes.push(accessing(data_node("get_1", "get_variable", &[], &[("result", "f32")]), "first"));
+
+            let mut exec_targets = vec!["set_2", "set_1"];
+            if reversed {
+                nodes.reverse();
+                exec_targets.reverse();
+            }
+            for node in nodes {
+                graph.add_node(node);
+            }
+            for target in exec_targets {
+                connect_exec(&mut graph, ("fs_1", "Body"), target);
+            }
+            connect(&mut graph, ("get_1", "result"), ("fs_out_1", "color"));
+            graph
+        };
+
+        let declarations = GraphDeclarations::new()
+            .with_variable("second", "f32", None)
+            .with_variable("first", "f32", None);
+        let wgsl_code = compile_fragment_declaring(&sibling_graph(false), &declarations)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+        let first = wgsl_code.find("    first = 1.0;\n").expect("first should be assigned");
+        let second = wgsl_code.find("    second = 2.0;\n").expect("second should be assigned");
+        assert!(first < second, "Independent nodes on the same exec output should run in ID order");
+
+        for reversed in [false, true, false, true] {
+            let recompiled = compile_fragment_declaring(&sibling_graph(reversed), &declarations)
+                .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+                .wgsl;
+            assert_eq!(recompiled, wgsl_code, "Output should be identical for the same graph");
+        }
+    }
+
+    /// Test: Graph names must be identifiers the generated code doesn't declare itself
+    ///
+    /// Graph structure:
+    /// declarations: <name>: vec4<f32>, albedo_map: texture_2d<f32>
+    /// texture_parameter(albedo_map)
+    /// get_variable(<name>) → fragment_output
+    #[test]
+    fn test_identifier_checks() {
+        init_logging();
+
+        for name in ["brightness", "loop_count", "_scale", "Tint2"] {
+            assert!(identifiers::is_identifier(name), "{} should be an identifier", name);
+        }
+        for name in ["", "_", "__scale", "2d", "a-b", "loop", "var", "fn", "let", "Self", "typeof"] {
+            assert!(!identifiers::is_identifier(name), "{} should not be an identifier", name);
+        }
+
+        let entry_points = EntryPointNames::default();
+        assert!(identifiers::check_name("brightness", &entry_points).is_ok());
+        for name in [
+            "output", "input", "VertexOutput", "MaterialParams", "material", "fragment_main",
+            "node_add", "value_add_1", "frag_uv", "vertex_position", "vertex_index", "global_invocation_id",
+        ] {
+            assert!(identifiers::check_name(name, &entry_points).is_err(), "{} is used by the generated code", name);
+        }
+
+        let variable_graph = |name: &str| {
+            let mut graph = GraphDescription::new("identifier_checks");
+            graph.add_node(entry_node("fs_1", "fragment_main"));
+            graph.add_node(accessing(data_node("get_1", "get_variable", &[], &[("result", "vec4<f32>")]), name));
+            graph.add_node(parameter_node("albedo_1", "texture_parameter", "albedo_map", "texture_2d<f32>"));
+            graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+            connect(&mut graph, ("get_1", "result"), ("fs_out_1", "color"));
+            graph
+        };
+
+        let declaring = |name: &str| GraphDeclarations::new()
+            .with_variable(name, "vec4<f32>", None)
+            .with_parameter("albedo_map", "texture_2d<f32>", None);
+
+        compile_fragment_declaring(&variable_graph("brightness"), &declaring("brightness"))
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+
+        for name in ["loop", "output", "fragment_main", "value_get_1", "albedo_map"] {
+            let failure = compile_fragment_declaring(&variable_graph(name), &declaring(name))
+                .expect_err("Variable names must not clash with WGSL or the generated code");
+            let errors: Vec<_> = failure.errors()
+                .filter(|error| error.code == DiagnosticCode::InvalidVariable)
+                .collect();
+            assert_eq!(errors.len(), 1, "Variable {} should be rejected once", name);
+            assert!(errors[0].node_id.is_none() && errors[0].message.contains(name),
+                "Error should name the declaration");
+        }
+
+        let mut keyword_parameter = variable_graph("brightness");
+        keyword_parameter.add_node(parameter_node("tint_1", "parameter", "var", "vec4<f32>"));
+        let failure = compile_fragment_declaring(
+            &keyword_parameter,
+            &declaring("brightness").with_parameter("var", "color", None),
+        ).expect_err("Parameter names must not be keywords");
+        let errors: Vec<_> = failure.errors().collect();
+        assert_eq!(errors.len(), 1, "The keyword should only be reported on its declaration");
+        assert!(errors[0].code == DiagnosticCode::InvalidParameter && errors[0].node_id.is_none()
+            && errors[0].message.contains("var"), "Error should name the declaration");
+
+        let mut options = CompileOptions::for_stages(&[ShaderStage::Fragment]);
+        options.entry_points.fragment = "VertexOutput".to_string();
+        let failure = compile_with_declarations(&variable_graph("brightness"), &declaring("brightness"), &options)
+            .expect_err("Entry point names must not clash with generated names");
+        assert!(failure.errors().all(|error| error.code == DiagnosticCode::InvalidOption));
+    }
+
+    /// Create a parameter node of `node_type` reading the parameter `name`
+    fn parameter_node(id: &str, node_type: &str, name: &str, output_type: &str) -> NodeInstance {
+        let mut node = data_node(id, node_type, &[], &[("result", output_type)]);
+        node.properties.insert("parameter".to_string(), PropertyValue::String(name.to_string()));
+        node
+    }
+
+    /// Test: Declared parameters are packed into the material uniform buffer
+    #[test]
+    fn test_uniform_parameters() {
+        init_logging();
+
+        let declaring = |roughness_default: &str| GraphDeclarations::new()
+            .with_parameter("tint", "color", Some("#ffffff"))
+            .with_parameter("roughness", "float", Some(roughness_default))
+            .with_parameter("enabled", "bool", None);
+        let parameter_graph = || {
+            let mut graph = GraphDescription::new("uniform_parameters");
+            graph.add_node(entry_node("fs_1", "fragment_main"));
+            graph.add_node(parameter_node("tint_1", "parameter", "tint", "vec4<f32>"));
+            graph.add_node(parameter_node("tint_2", "parameter", "tint", "vec4<f32>"));
+            graph.add_node(parameter_node("roughness_1", "parameter", "roughness", "f32"));
+            graph.add_node(parameter_node("enabled_1", "parameter", "enabled", "bool"));
+
+            graph.add_node(data_node("mul_1", "multiply", &[("a", "vec4<f32>"), ("b", "f32")], &[("result", "vec4<f32>")]));
+            graph.add_node(data_node(
+                "select_1", "select",
+                &[("false_value", "vec4<f32>"), ("true_value", "vec4<f32>"), ("condition", "bool")],
+                &[("result", "vec4<f32>")],
+            ));
+            graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+
+            connect(&mut graph, ("tint_1", "result"), ("mul_1", "a"));
+            connect(&mut graph, ("roughness_1", "result"), ("mul_1", "b"));
+            connect(&mut graph, ("tint_2", "result"), ("select_1", "false_value"));
+            connect(&mut graph, ("mul_1", "result"), ("select_1", "true_value"));
+            connect(&mut graph, ("enabled_1", "result"), ("select_1", "condition"));
+            connect(&mut graph, ("select_1", "result"), ("fs_out_1", "color"));
+            graph
+        };
+
+        let wgsl_code = compile_fragment_declaring(&parameter_graph(), &declaring("0.5"))
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+        assert!(wgsl_code.contains(
+            "struct MaterialParams {\n    tint: vec4<f32>, // offset 0\n    enabled: u32, // offset 16\n    roughness: f32, // offset 20\n}"
+        ), "Fields should be ordered by alignment with WGSL uniform offsets");
+        assert!(wgsl_code.contains("@group(0) @binding(0) var<uniform> material: MaterialParams;"),
+            "Uniform buffer should be bound");
+        assert!(wgsl_code.contains("material.tint * vec4<f32>(material.roughness)"),
+            "Parameters should read their uniform field");
+        assert!(wgsl_code.contains("(material.enabled != 0u)"),
+            "Bool parameters should be stored as u32");
+        assert_eq!(wgsl_code.matches("tint: vec4<f32>").count(), 1,
+            "Parameters read by several nodes should get one field");
+
+        let error = compile_fragment_declaring(&parameter_graph(), &declaring("rough"))
+            .expect_err("Invalid parameter defaults should be rejected");
+        assert!(error.to_string().contains("roughness"), "Error should name the parameter");
+
+        // Declared parameters are part of the layout even when no node reads them
+        let unread = compile_fragment_declaring(&parameter_graph(), &declaring("0.5").with_parameter("metallic", "f32", None))
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        assert!(unread.contains("    metallic: f32, // offset 20\n"), "Unread parameters should get a field");
+
+        let undeclared = GraphDeclarations::new()
+            .with_parameter("tint", "color", None)
+            .with_parameter("enabled", "bool", None);
+        let failure = compile_fragment_declaring(&parameter_graph(), &undeclared)
+            .expect_err("Nodes must read declared parameters");
+        let nodes: Vec<_> = failure.errors()
+            .filter(|error| error.code == DiagnosticCode::InvalidParameter)
+            .filter_map(|error| error.node_id.as_deref())
+            .collect();
+        assert_eq!(nodes, vec!["roughness_1"], "The node reading the undeclared parameter should be reported");
+
+        let mut texture_read = parameter_graph();
+        texture_read.add_node(parameter_node("albedo_1", "parameter", "albedo_map", "texture_2d<f32>"));
+        let failure = compile_fragment_declaring(
+            &texture_read,
+            &declaring("0.5").with_parameter("albedo_map", "texture_2d<f32>", None),
+        ).expect_err("Uniform parameter nodes can't read textures");
+        assert!(failure.errors().any(|error| error.code == DiagnosticCode::InvalidParameter
+            && error.node_id.as_deref() == Some("albedo_1")));
+    }
+
+    /// Test: Texture and sampler parameters become deduplicated bindings
+    #[test]
+    fn test_resource_bindings() {
+        init_logging();
+
+        let declaring = |sampler_type: &str| GraphDeclarations::new()
+            .with_parameter("tint", "color", None)
+            .with_parameter("albedo_map", "texture_2d<f32>", None)
+            .with_parameter("linear_sampler", sampler_type, None)
+            .with_parameter("shadow_map", "texture_depth_2d", None)
+            .with_parameter("shadow_sampler", "comparison", None);
+        let resource_graph = {
+            let mut graph = GraphDescription::new("resource_bindings");
+            graph.add_node(entry_node("fs_1", "fragment_main"));
+            graph.add_node(parameter_node("tint_1", "parameter", "tint", "vec4<f32>"));
+            graph.add_node(data_node("uv_1", "frag_uv", &[], &[("uv", "vec2<f32>")]));
+
+            // The same texture and sampler are read by two nodes each
+            for id in ["albedo_1", "albedo_2"] {
+                graph.add_node(parameter_node(id, "texture_parameter", "albedo_map", "texture_2d<f32>"));
+            }
+            for id in ["sampler_1", "sampler_2"] {
+                graph.add_node(parameter_node(id, "sampler_parameter", "linear_sampler", "sampler"));
+            }
+            graph.add_node(parameter_node("shadow_map_1", "texture_parameter", "shadow_map", "texture_depth_2d"));
+            graph.add_node(parameter_node(
+                "shadow_sampler_1", "sampler_parameter", "shadow_sampler", "sampler_comparison",
+            ));
+
+            let sample_inputs = [("texture", "texture_2d<f32>"), ("sampler", "sampler"), ("uv", "vec2<f32>")];
+            for (id, texture, sampler) in [("sample_1", "albedo_1", "sampler_1"), ("sample_2", "albedo_2", "sampler_2")] {
+                graph.add_node(data_node(id, "sample_texture", &sample_inputs, &[("result", "vec4<f32>")]));
+                connect(&mut graph, (texture, "result"), (id, "texture"));
+                connect(&mut graph, (sampler, "result"), (id, "sampler"));
+                connect(&mut graph, ("uv_1", "uv"), (id, "uv"));
+            }
+            graph.add_node(data_node(
+                "shadow_1", "sample_texture_compare",
+                &[("texture", "texture_depth_2d"), ("sampler", "sampler_comparison"), ("uv", "vec2<f32>"), ("depth_ref", "f32")],
+                &[("result", "f32")],
+            ));
+            connect(&mut graph, ("shadow_map_1", "result"), ("shadow_1", "texture"));
+            connect(&mut graph, ("shadow_sampler_1", "result"), ("shadow_1", "sampler"));
+            connect(&mut graph, ("uv_1", "uv"), ("shadow_1", "uv"));
+
+            for id in ["mul_1", "mul_2", "mul_3"] {
+                graph.add_node(data_node(id, "multiply", &[("a", "vec4<f32>"), ("b", "vec4<f32>")], &[("result", "vec4<f32>")]));
+            }
+            connect(&mut graph, ("sample_1", "result"), ("mul_1", "a"));
+            connect(&mut graph, ("sample_2", "result"), ("mul_1", "b"));
+            connect(&mut graph, ("mul_1", "result"), ("mul_2", "a"));
+            connect(&mut graph, ("shadow_1", "result"), ("mul_2", "b"));
+            connect(&mut graph, ("mul_2", "result"), ("mul_3", "a"));
+            connect(&mut graph, ("tint_1", "result"), ("mul_3", "b"));
+
+            graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+            connect(&mut graph, ("mul_3", "result"), ("fs_out_1", "color"));
+            graph
+        };
+
+        let compiled = compile_fragment_declaring(&resource_graph, &declaring("filtering"))
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        let wgsl_code = &compiled.wgsl;
+        println!("{}", wgsl_code);
+        assert!(wgsl_code.contains(concat!(
+            "@group(0) @binding(0) var<uniform> material: MaterialParams;\n",
+            "@group(0) @binding(1) var albedo_map: texture_2d<f32>;\n",
+            "@group(0) @binding(2) var shadow_map: texture_depth_2d;\n",
+            "@group(0) @binding(3) var linear_sampler: sampler;\n",
+            "@group(0) @binding(4) var shadow_sampler: sampler_comparison;\n",
+        )), "Resources should follow the uniform buffer, textures first, each sorted by name");
+        assert_eq!(wgsl_code.matches("textureSample(albedo_map, linear_sampler, frag_uv)").count(), 2,
+            "Both samples should read the shared bindings");
+        assert!(wgsl_code.contains("textureSampleCompare(shadow_map, shadow_sampler, frag_uv, 0.0)"),
+            "Comparison samplers should be usable for depth comparisons");
+
+        let sampler_type = |compiled: &CompiledShader, binding: u32| {
+            match compiled.reflection.binding(0, binding).map(|binding| &binding.resource) {
+                Some(ResourceReflection::Sampler { binding_type }) => Some(*binding_type),
+                _ => None,
+            }
+        };
+        assert_eq!(sampler_type(&compiled, 3), Some(SamplerBindingType::Filtering));
+        assert_eq!(sampler_type(&compiled, 4), Some(SamplerBindingType::Comparison));
+
+        let non_filtering = compile_fragment_declaring(&resource_graph, &declaring("non_filtering"))
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        assert!(non_filtering.wgsl.contains("@group(0) @binding(3) var linear_sampler: sampler;\n"),
+            "Non-filtering samplers should be plain WGSL samplers");
+        assert_eq!(sampler_type(&non_filtering, 3), Some(SamplerBindingType::NonFiltering),
+            "The declared type should select the sampler binding type");
+
+        let error = compile_fragment_declaring(&resource_graph, &declaring("comparison"))
+            .expect_err("Comparison samplers should not be accepted by sample_texture");
+        assert!(error.to_string().contains("sampler_comparison"), "Error should name the sampler type");
+    }
+
+    /// Test: Reflection describes entry points, bindings and vertex inputs
+    #[test]
+    fn test_shader_reflection() {
+        init_logging();
+
+        let mut graph = vertex_color_graph(true);
+        graph.add_node(parameter_node("tint_1", "parameter", "tint", "vec4<f32>"));
+        graph.add_node(parameter_node("roughness_1", "parameter", "roughness", "f32"));
+        graph.add_node(parameter_node("albedo_1", "texture_parameter", "albedo_map", "texture_2d<f32>"));
+        let declarations = GraphDeclarations::new()
+            .with_parameter("tint", "color", None)
+            .with_parameter("roughness", "float", None)
+            .with_parameter("albedo_map", "texture_2d<f32>", None);
+
+        let options = CompileOptions::for_stages(&[ShaderStage::Vertex, ShaderStage::Fragment]);
+        let compiled = compile_with_declarations(&graph, &declarations, &options)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        let reflection = &compiled.reflection;
+        println!("{:#?}", reflection);
+
+        let entry_points: Vec<_> = reflection.entry_points.iter()
+            .map(|entry_point| (entry_point.name.as_str(), entry_point.stage, entry_point.workgroup_size))
+            .collect();
+        assert_eq!(entry_points, vec![
+            ("vertex_main", ShaderStage::Vertex, None),
+            ("fragment_main", ShaderStage::Fragment, None),
+        ]);
+
+        assert_eq!(reflection.vertex_inputs.len(), 1, "Only vertex_color is read");
+        let color = &reflection.vertex_inputs[0];
+        assert_eq!((color.name.as_str(), color.location, color.format.as_str(), color.size),
+            ("vertex_color", 3, "float32x4", 16));
+
+        let buffer = reflection.uniform_buffer().expect("Parameters should be reflected");
+        assert_eq!(buffer.size, 32);
+        let fields: Vec<_> = buffer.fields.iter()
+            .map(|field| (field.name.as_str(), field.wgsl_type.as_str(), field.offset, field.size))
+            .collect();
+        assert_eq!(fields, vec![("tint", "vec4<f32>", 0, 16), ("roughness", "f32", 16, 4)]);
+
+        let albedo = reflection.binding(0, 1).expect("Texture should follow the uniform buffer");
+        assert_eq!(albedo.name, "albedo_map");
+        assert!(albedo.stages.is_empty(), "Parameters no entry function reads should be visible to no stage");
+        assert!(matches!(
+            albedo.resource,
+            ResourceReflection::Texture { dimension: TextureDimension::D2, sample_type: TextureSampleType::Float, .. }
+        ));
+
+        let json = serde_json::to_string(&compiled).expect("Compiled shaders should serialize");
+        let parsed: CompiledShader = serde_json::from_str(&json).expect("Compiled shaders should deserialize");
+        assert_eq!(parsed, compiled, "Serialization should round-trip");
+    }
+
+    /// Test: Bindings are visible to the stages whose entry functions read them
+    ///
+    /// Graph structure:
+    /// offset parameter → vec4.x → vertex_output
+    /// sample_texture(albedo_map, linear_sampler) * tint parameter → fragment_output
+    #[test]
+    fn test_binding_stages() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("binding_stages");
+        graph.add_node(entry_node("vs_1", "vertex_main"));
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+
+        graph.add_node(parameter_node("offset_1", "parameter", "offset", "f32"));
+        let mut position = data_node(
+            "pos_1", "vec4",
+            &[("x", "f32"), ("y", "f32"), ("z", "f32"), ("w", "f32")],
+            &[("result", "vec4<f32>")],
+        );
+        position.properties.insert("pos_1_w".to_string(), PropertyValue::Number(1.0));
+        graph.add_node(position);
+        graph.add_node(data_node("vs_out_1", "vertex_output", &[("position", "vec4<f32>")], &[]));
+        connect(&mut graph, ("offset_1", "result"), ("pos_1", "x"));
+        connect(&mut graph, ("pos_1", "result"), ("vs_out_1", "position"));
+
+        graph.add_node(parameter_node("albedo_1", "texture_parameter", "albedo_map", "texture_2d<f32>"));
+        graph.add_node(parameter_node("sampler_1", "sampler_parameter", "linear_sampler", "sampler"));
+        graph.add_node(parameter_node("tint_1", "parameter", "tint", "vec4<f32>"));
+        graph.add_node(data_node(
+            "sample_1", "sample_texture",
+            &[("texture", "texture_2d<f32>"), ("sampler", "sampler"), ("uv", "vec2<f32>")],
+            &[("result", "vec4<f32>")],
+        ));
+        graph.add_node(data_node("mul_1", "multiply", &[("a", "vec4<f32>"), ("b", "vec4<f32>")], &[("result", "vec4<f32>")]));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut graph, ("albedo_1", "result"), ("sample_1", "texture"));
+        connect(&mut graph, ("sampler_1", "result"), ("sample_1", "sampler"));
+        connect(&mut graph, ("sample_1", "result"), ("mul_1", "a"));
+        connect(&mut graph, ("tint_1", "result"), ("mul_1", "b"));
+        connect(&mut graph, ("mul_1", "result"), ("fs_out_1", "color"));
+
+        let declarations = GraphDeclarations::new()
+            .with_parameter("offset", "float", None)
+            .with_parameter("tint", "color", None)
+            .with_parameter("albedo_map", "texture_2d<f32>", None)
+            .with_parameter("linear_sampler", "filtering", None);
+        let options = CompileOptions::for_stages(&[ShaderStage::Vertex, ShaderStage::Fragment]);
+        let compiled = compile_with_declarations(&graph, &declarations, &options)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        println!("{:#?}", compiled.reflection.bindings);
+
+        let stages: Vec<_> = compiled.reflection.bindings.iter()
+            .map(|binding| (binding.name.as_str(), binding.stages.clone()))
+            .collect();
+        assert_eq!(stages, vec![
+            ("material", vec![ShaderStage::Vertex, ShaderStage::Fragment]),
+            ("albedo_map", vec![ShaderStage::Fragment]),
+            ("linear_sampler", vec![ShaderStage::Fragment]),
+        ], "The uniform buffer is read by both stages, the texture and sampler by the fragment stage only");
+    }
+
+    /// Test: Pure nodes read more than once are bound to a `let` by default,
+    /// expensive ones at `OptimizationLevel::Full`
+    ///
+    /// Graph structure:
+    /// sample_texture(albedo_map, linear_sampler, frag_uv) → multiply.a
+    ///                                                     → multiply.b → fragment_output
+    #[test]
+    fn test_common_subexpressions() {
+        init_logging();
+
+        // Samples albedo_map at frag_uv
+        let sample_node = |graph: &mut GraphDescription, id: &str| {
+            graph.add_node(data_node(
+                id, "sample_texture",
+                &[("texture", "texture_2d<f32>"), ("sampler", "sampler"), ("uv", "vec2<f32>")],
+                &[("result", "vec4<f32>")],
+            ));
+            connect(graph, ("albedo_1", "result"), (id, "texture"));
+            connect(graph, ("sampler_1", "result"), (id, "sampler"));
+            connect(graph, ("uv_1", "uv"), (id, "uv"));
+        };
+        let multiply_node = |graph: &mut GraphDescription, id: &str, a: &str, b: &str| {
+            graph.add_node(data_node(id, "multiply", &[("a", "vec4<f32>"), ("b", "vec4<f32>")], &[("result", "vec4<f32>")]));
+            connect(graph, (a, "result"), (id, "a"));
+            connect(graph, (b, "result"), (id, "b"));
+        };
+        let sample_graph = |name: &str| {
+            let mut graph = GraphDescription::new(name);
+            graph.add_node(entry_node("fs_1", "fragment_main"));
+            graph.add_node(data_node("uv_1", "frag_uv", &[], &[("uv", "vec2<f32>")]));
+            graph.add_node(parameter_node("albedo_1", "texture_parameter", "albedo_map", "texture_2d<f32>"));
+            graph.add_node(parameter_node("sampler_1", "sampler_parameter", "linear_sampler", "sampler"));
+            graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+            graph
+        };
+        let declarations = GraphDeclarations::new()
+            .with_parameter("albedo_map", "texture_2d<f32>", None)
+            .with_parameter("linear_sampler", "filtering", None);
+
+        let mut graph = sample_graph("common_subexpressions");
+        sample_node(&mut graph, "sample_1");
+        multiply_node(&mut graph, "mul_1", "sample_1", "sample_1");
+        connect(&mut graph, ("mul_1", "result"), ("fs_out_1", "color"));
+
+        let compiled = compile_fragment_declaring(&graph, &declarations)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        let wgsl_code = &compiled.wgsl;
+        println!("{}", wgsl_code);
+        assert_eq!(wgsl_code.matches("textureSample(").count(), 1, "The texture should be sampled once");
+        assert!(wgsl_code.contains(
+            "    let value_sample_1 = textureSample(albedo_map, linear_sampler, frag_uv);\n    return value_sample_1 * value_sample_1;\n"
+        ), "The sample should be bound right before its first use");
+        assert!(!wgsl_code.contains("let value_albedo_1"), "Resources can't be bound to a let");
+
+        let let_line = wgsl_code.lines()
+            .position(|line| line.contains("let value_sample_1"))
+            .expect("The sample should be bound") as u32 + 1;
+        assert_eq!(compiled.source_map.node_at_line(let_line), Some("sample_1"),
+            "The let should map to the hoisted node");
+
+        let mut options = CompileOptions::for_stages(&[ShaderStage::Fragment]);
+        options.optimization = OptimizationLevel::None;
+        let inlined = compile_with_declarations(&graph, &declarations, &options)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        assert_eq!(inlined.matches("textureSample(").count(), 2,
+            "Without optimization pure nodes should be inlined at every read");
+
+        // IDs that sanitize to the same name still get their own let
+        let mut colliding = sample_graph("colliding_names");
+        sample_node(&mut colliding, "sample-1");
+        sample_node(&mut colliding, "sample_1");
+        multiply_node(&mut colliding, "mul_1", "sample-1", "sample-1");
+        multiply_node(&mut colliding, "mul_2", "sample_1", "sample_1");
+        multiply_node(&mut colliding, "mul_3", "mul_1", "mul_2");
+        connect(&mut colliding, ("mul_3", "result"), ("fs_out_1", "color"));
+
+        let wgsl_code = compile_fragment_declaring(&colliding, &declarations)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", wgsl_code);
+        assert_eq!(wgsl_code.matches("let value_sample_1 = ").count(), 1);
+        assert_eq!(wgsl_code.matches("let value_sample_1_2 = ").count(), 1);
+        assert!(wgsl_code.contains("(value_sample_1 * value_sample_1) * (value_sample_1_2 * value_sample_1_2)"),
+            "Each node should read its own let");
+
+        // An expensive node read once is only bound at Full
+        let mut expensive = sample_graph("expensive");
+        sample_node(&mut expensive, "sample_1");
+        sample_node(&mut expensive, "sample_2");
+        multiply_node(&mut expensive, "mul_1", "sample_1", "sample_2");
+        connect(&mut expensive, ("mul_1", "result"), ("fs_out_1", "color"));
+
+        let basic = compile_fragment_declaring(&expensive, &declarations)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        assert!(!basic.contains("let value_"), "Nodes read once should be inlined by default");
+
+        options.optimization = OptimizationLevel::Full;
+        let full = compile_with_declarations(&expensive, &declarations, &options)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .wgsl;
+        println!("{}", full);
+        assert!(full.contains("    let value_mul_1 = textureSample("), "Two samples should pass the cost threshold");
+        assert!(full.contains("    return value_mul_1;\n"));
+    }
+
+    /// Test: The uniform layout is mirrored by a padded `#[repr(C)]` Rust struct
+    #[test]
+    fn test_rust_uniform_struct() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("rust_uniforms");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(parameter_node("tint_1", "parameter", "tint", "vec4<f32>"));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut graph, ("tint_1", "result"), ("fs_out_1", "color"));
+        let declarations = GraphDeclarations::new()
+            .with_parameter("tint", "color", None)
+            .with_parameter("emission", "vec3<f32>", None)
+            .with_parameter("roughness", "float", None);
+
+        let compiled = compile_fragment_declaring(&graph, &declarations)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        let rust_code = compiled.reflection.uniform_buffer()
+            .expect("Parameters should be reflected")
+            .generate_rust_struct("MaterialParams")
+            .unwrap_or_else(|e| panic!("✗ Rust struct generation failed: {}", e));
+        println!("{}", rust_code);
+
+        assert!(rust_code.contains("#[repr(C)]\n"), "Struct should have a C layout");
+        assert!(rust_code.contains("bytemuck::Pod, bytemuck::Zeroable"), "Struct should be bytemuck-compatible");
+        assert!(rust_code.contains(concat!(
+            "pub struct MaterialParams {\n",
+            "    pub emission: [f32; 3], // offset 0\n",
+            "    pub _pad0: [u32; 1],\n",
+            "    pub tint: [f32; 4], // offset 16\n",
+            "    pub roughness: f32, // offset 32\n",
+            "    pub _pad1: [u32; 3],\n",
+            "}\n",
+        )), "vec3 fields and the struct tail should be padded to WGSL alignment");
+        assert!(rust_code.contains("assert!(std::mem::size_of::<MaterialParams>() == 48);"),
+            "Struct size should be checked at compile time");
+
+        // Padding fields skip names taken by parameters
+        let padded_name = GraphDeclarations::new()
+            .with_parameter("tint", "color", None)
+            .with_parameter("_pad0", "vec3<f32>", None);
+        let rust_code = compile_fragment_declaring(&graph, &padded_name)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e))
+            .reflection
+            .uniform_buffer()
+            .expect("Parameters should be reflected")
+            .generate_rust_struct("MaterialParams")
+            .unwrap_or_else(|e| panic!("✗ Rust struct generation failed: {}", e));
+        println!("{}", rust_code);
+        assert!(rust_code.contains(concat!(
+            "    pub _pad0: [f32; 3], // offset 0\n",
+            "    pub _pad1: [u32; 1],\n",
+            "    pub tint: [f32; 4], // offset 16\n",
+        )), "Padding should not reuse the name of the _pad0 parameter");
+
+        // WGSL reserves most Rust keywords too, so build the layout by hand
+        let field = |name: &str, offset: u32| UniformFieldReflection {
+            name: name.to_string(),
+            wgsl_type: "f32".to_string(),
+            offset,
+            size: 4,
+            align: 4,
+            default: None,
+        };
+        let buffer = UniformBufferReflection {
+            struct_name: "MaterialParams".to_string(),
+            size: 32,
+            fields: ["self", "self_", "Self", "crate", "super", "try", "box", "gen"]
+                .into_iter()
+                .enumerate()
+                .map(|(index, name)| field(name, index as u32 * 4))
+                .collect(),
+        };
+        let rust_code = buffer.generate_rust_struct("KeywordParams")
+            .unwrap_or_else(|e| panic!("✗ Rust struct generation failed: {}", e));
+        println!("{}", rust_code);
+        for declaration in [
+            "pub self__: f32", "pub self_: f32", "pub Self_: f32", "pub crate_: f32", "pub super_: f32",
+            "pub r#try: f32", "pub r#box: f32", "pub r#gen: f32",
+        ] {
+            assert!(rust_code.contains(declaration), "Expected field `{}`", declaration);
+        }
+        assert!(!rust_code.contains("r#self") && !rust_code.contains("r#crate"),
+            "self, Self, super and crate can't be raw identifiers");
+
+        for name in ["", "Material Params", "2Params", "struct", "Self", "crate", "_"] {
+            assert!(buffer.generate_rust_struct(name).is_err(), "'{}' should be rejected as struct name", name);
+        }
+    }
+
+    /// Test: Compile options control naming, bindings and what gets emitted
+    ///
+    /// Graph structure:
+    /// fragment_main → for_loop, tint parameter → fragment_output
+    /// frag_normal → desaturate (unused)
+    #[test]
+    fn test_compile_options() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("compile_options");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        let mut for_loop = with_exec_pins(
+            data_node("loop_1", "for_loop", &[("count", "u32")], &[("index", "u32")]),
+            &["Body", "Completed"],
+        );
+        for_loop.properties.insert("loop_1_count".to_string(), PropertyValue::Number(4.0));
+        graph.add_node(for_loop);
+        graph.add_node(parameter_node("tint_1", "parameter", "tint", "vec4<f32>"));
+        graph.add_node(data_node("normal_1", "frag_normal", &[], &[("result", "vec3<f32>")]));
+        graph.add_node(data_node(
+            "desat_1", "desaturate",
+            &[("color", "vec3<f32>"), ("amount", "f32")],
+            &[("result", "vec3<f32>")],
+        ));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect_exec(&mut graph, ("fs_1", "Body"), "loop_1");
+        connect(&mut graph, ("tint_1", "result"), ("fs_out_1", "color"));
+        connect(&mut graph, ("normal_1", "result"), ("desat_1", "color"));
+
+        let declarations = GraphDeclarations::new().with_parameter("tint", "color", None);
+
+        let mut options = CompileOptions::for_stages(&[ShaderStage::Fragment]);
+        options.entry_points.fragment = "material_fs".to_string();
+        options.material_group = 2;
+        options.header = false;
+        options.comments = false;
+        options.debug_annotations = true;
+        options.features.insert("f16".to_string());
+
+        let compiled = compile_with_declarations(&graph, &declarations, &options)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        let wgsl_code = &compiled.wgsl;
+        println!("{}", wgsl_code);
+
+        assert!(wgsl_code.starts_with("enable f16;\n"), "Features should be enabled before anything else");
+        assert!(!wgsl_code.contains("// Auto-generated"), "Header should be omitted");
+        assert!(!wgsl_code.contains("// offset") && !wgsl_code.contains("// Material parameters"),
+            "Comments should be omitted");
+        assert!(wgsl_code.contains("fn material_fs("), "Entry point should be renamed");
+        assert_eq!(compiled.reflection.entry_point(ShaderStage::Fragment).map(|e| e.name.as_str()),
+            Some("material_fs"), "Reflection should use the entry point name");
+        assert!(wgsl_code.contains("@group(2) @binding(0) var<uniform> material: MaterialParams;"),
+            "Material should be bound in the requested group");
+        assert!(compiled.reflection.binding(2, 0).is_some(), "Reflection should use the requested group");
+        assert!(wgsl_code.contains("    // loop_1 (for_loop)\n    for ("), "Statements should be annotated");
+        assert!(!wgsl_code.contains("fn node_desaturate"), "Unreachable helpers should be skipped");
+
+        options.optimization = OptimizationLevel::None;
+        let unoptimized = compile_with_declarations(&graph, &declarations, &options)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        assert!(unoptimized.wgsl.contains("fn node_desaturate"), "Without optimization every helper is emitted");
+
+        options.entry_points.fragment = "fragment main".to_string();
+        let failure = compile_with_declarations(&graph, &declarations, &options)
+            .expect_err("Entry point names must be identifiers");
+        assert!(failure.errors().all(|error| error.code == DiagnosticCode::InvalidOption));
+
+        let failure = compile_with_declarations(
+            &graph,
+            &declarations,
+            &CompileOptions::for_stages(&[ShaderStage::Vertex, ShaderStage::Compute]),
+        )
+            .expect_err("Vertex and compute can't be compiled together");
+        assert!(failure.to_string().contains("Compute"), "Error should name the stages");
+    }
+
+    /// Test: Generated WGSL is checked with naga, errors point at the generating node
+    #[test]
+    fn test_wgsl_validation() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("wgsl_validation");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(parameter_node("tint_1", "parameter", "tint", "vec4<f32>"));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut graph, ("tint_1", "result"), ("fs_out_1", "color"));
+        let declarations = GraphDeclarations::new().with_parameter("tint", "color", None);
+
+        let mut options = CompileOptions::for_stages(&[ShaderStage::Fragment]);
+        options.validate = true;
+        compile_with_declarations(&graph, &declarations, &options)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+
+        // WGSL lets declarations shadow the predeclared `vec4`, naga doesn't
+        options.entry_points.fragment = "vec4".to_string();
+        let failure = compile_with_declarations(&graph, &declarations, &options)
+            .expect_err("A predeclared type as entry point name should fail validation");
+        println!("{}", failure);
+
+        let error = failure.errors().next().expect("Validation should report an error");
+        assert_eq!(error.code, DiagnosticCode::InvalidWgsl);
+        assert_eq!(error.node_id.as_deref(), Some("fs_1"), "Error should point at the entry node");
+        let span = error.span.expect("Error should have a source span");
+        assert!(span.line > 1 && span.column > 1, "Span should locate the entry point name");
+    }
+
+    /// Test: The generated module is translated to other shading languages
+    #[test]
+    fn test_cross_compile() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("cross_compile");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(parameter_node("tint_1", "parameter", "tint", "vec4<f32>"));
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut graph, ("tint_1", "result"), ("fs_out_1", "color"));
+        let declarations = GraphDeclarations::new().with_parameter("tint", "color", None);
+
+        let compile = |target: CompileTarget| {
+            let options = CompileOptions {
+                target,
+                ..CompileOptions::for_stages(&[ShaderStage::Fragment])
+            };
+            compile_with_declarations(&graph, &declarations, &options)
+                .unwrap_or_else(|e| panic!("✗ Compilation to {} failed: {}", target, e))
+        };
+
+        assert!(compile(CompileTarget::Wgsl).targets.is_empty(), "WGSL needs no translation");
+
+        let spirv = compile(CompileTarget::SpirV);
+        assert_eq!(spirv.targets.len(), 1, "SPIR-V should be a single module");
+        let words = spirv.targets[0].code.as_spirv().expect("SPIR-V output should be binary");
+        assert_eq!(words.first(), Some(&0x0723_0203), "Output should start with the SPIR-V magic number");
+        assert_eq!(spirv.targets[0].stages, vec![ShaderStage::Fragment]);
+
+        let glsl = compile(CompileTarget::Glsl { version: 300 });
+        let output = &glsl.targets[0];
+        let code = output.code.as_text().expect("GLSL output should be text");
+        println!("{}", code);
+        assert!(code.starts_with("#version 300 es"), "Version 300 should select GLSL ES");
+        assert_eq!(output.entry_points, vec!["main".to_string()], "GLSL entry points are named main");
+        assert!(output.bindings.iter().any(|binding| binding.group == 0 && binding.binding == 0),
+            "The uniform buffer should be named in the reflection");
+
+        let hlsl = compile(CompileTarget::Hlsl { shader_model: 50 });
+        assert_eq!(hlsl.targets[0].entry_points, vec!["fragment_main".to_string()]);
+        assert!(hlsl.targets[0].code.as_text().is_some_and(|code| code.contains("fragment_main")));
+
+        let msl = compile(CompileTarget::Msl);
+        assert!(msl.targets[0].code.as_text().is_some_and(|code| code.contains("metal_stdlib")),
+            "MSL output should include the Metal standard library");
+
+        // Reflection doesn't depend on the target
+        assert_eq!(spirv.reflection, hlsl.reflection);
+    }
+
+    /// Test: Spans of the generated WGSL map back to the nodes and pins they come from
+    ///
+    /// Graph structure:
+    /// frag_normal → desaturate.color (amount = 0.5) → fragment_output
+    #[test]
+    fn test_source_map() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("source_map");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(data_node("normal_1", "frag_normal", &[], &[("result", "vec3<f32>")]));
+        let mut desaturate = data_node(
+            "desat_1", "desaturate",
+            &[("color", "vec3<f32>"), ("amount", "f32")],
+            &[("result", "vec3<f32>")],
+        );
+        desaturate.properties.insert("desat_1_amount".to_string(), PropertyValue::Number(0.5));
+        graph.add_node(desaturate);
+        graph.add_node(data_node("fs_out_1", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut graph, ("normal_1", "result"), ("desat_1", "color"));
+        connect(&mut graph, ("desat_1", "result"), ("fs_out_1", "color"));
+
+        let compiled = compile_fragment_shader(&graph)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        let lines: Vec<&str> = compiled.wgsl.lines().collect();
+        println!("{}", compiled.wgsl);
+
+        // The node_desaturate helper comes first and has a return of its own
+        let signature_line = lines.iter().position(|line| line.starts_with("fn fragment_main(")).unwrap() as u32 + 1;
+        let return_line = lines.iter()
+            .skip(signature_line as usize)
+            .position(|line| line.trim_start().starts_with("return"))
+            .unwrap() as u32 + signature_line + 1;
+        assert_eq!(compiled.source_map.node_at_line(return_line), Some("fs_out_1"),
+            "The return statement should map to the output node");
+        assert_eq!(compiled.source_map.node_at_line(signature_line), Some("fs_1"),
+            "The entry function should map to the entry node");
+
+        let span_text = |node_id: &str, pin_id: &str| {
+            let entry = compiled.source_map.spans_of(node_id)
+                .find(|entry| entry.kind == SpanKind::Expression && entry.pin_id.as_deref() == Some(pin_id))
+                .unwrap_or_else(|| panic!("No span for {}", pin_id));
+            let line = lines[entry.span.line as usize - 1];
+            let start = entry.span.column as usize - 1;
+            (entry.span, &line[start..start + entry.span.length as usize])
+        };
+        assert_eq!(span_text("normal_1", "normal_1_result").1, "frag_normal");
+        assert_eq!(span_text("desat_1", "desat_1_amount").1, "0.5", "Constants should map to their input pin");
+        let (desaturate_span, text) = span_text("desat_1", "desat_1_result");
+        assert_eq!(text, "node_desaturate(frag_normal, 0.5)");
+        assert_eq!(desaturate_span.line, return_line);
+
+        let (normal_span, _) = span_text("normal_1", "normal_1_result");
+        let innermost: Vec<_> = compiled.source_map.entries_at(normal_span.line, normal_span.column)
+            .iter()
+            .map(|entry| entry.node_id.as_str())
+            .collect();
+        assert_eq!(innermost, vec!["normal_1", "desat_1", "fs_out_1"],
+            "Entries at a position should be listed innermost first");
+
+        let json = serde_json::to_string(&compiled.source_map).expect("Source map should serialize");
+        let parsed: SourceMap = serde_json::from_str(&json).expect("Source map should deserialize");
+        assert_eq!(parsed, compiled.source_map, "Serialization should round-trip");
+    }
+
+    /// Test: Span columns and lengths count bytes, also around multi-byte text
+    ///
+    /// Graph structure:
+    /// frag_normal (ID `normale_ü`) → desaturate.color (ID `entsättigung_1`)
+    /// → fragment_output (ID `ausgabe_ü`)
+    #[test]
+    fn test_source_map_byte_columns() {
+        init_logging();
+
+        let mut graph = GraphDescription::new("source_map_bytes");
+        graph.add_node(entry_node("fs_1", "fragment_main"));
+        graph.add_node(data_node("normale_ü", "frag_normal", &[], &[("result", "vec3<f32>")]));
+        graph.add_node(data_node(
+            "entsättigung_1", "desaturate",
+            &[("color", "vec3<f32>"), ("amount", "f32")],
+            &[("result", "vec3<f32>")],
+        ));
+        graph.add_node(data_node("ausgabe_ü", "fragment_output", &[("color", "vec4<f32>")], &[]));
+        connect(&mut graph, ("normale_ü", "result"), ("entsättigung_1", "color"));
+        connect(&mut graph, ("entsättigung_1", "result"), ("ausgabe_ü", "color"));
+
+        let mut options = CompileOptions::for_stages(&[ShaderStage::Fragment]);
+        options.debug_annotations = true;
+        let compiled = compile_with_options(&graph, &options)
+            .unwrap_or_else(|e| panic!("✗ Shader compilation failed: {}", e));
+        println!("{}", compiled.wgsl);
+        assert!(compiled.wgsl.contains("// ausgabe_ü (fragment_output)\n"),
+            "Annotations should keep multi-byte node IDs");
+
+        let lines: Vec<&str> = compiled.wgsl.lines().collect();
+        for entry in &compiled.source_map.entries {
+            let line = lines[entry.span.line as usize - 1];
+            let start = entry.span.column as usize - 1;
+            assert!(line.get(start..start + entry.span.length as usize).is_some(),
+                "Span {:?} of {} should cover whole characters", entry.span, entry.node_id);
+        }
+
+        let normal = compiled.source_map.spans_of("normale_ü")
+            .find(|entry| entry.kind == SpanKind::Expression)
+            .expect("The normal should have an expression span");
+        let line = lines[normal.span.line as usize - 1];
+        let start = normal.span.column as usize - 1;
+        assert_eq!(&line[start..start + normal.span.length as usize], "frag_normal");
+        assert_eq!(normal.span.column as usize, line.find("frag_normal").unwrap() + 1,
+            "Columns should be byte offsets");
+    }
+}
@@ -9,6 +9,9 @@
 //! - **Texture**: Texture sampling operations
 //! - **Input**: Shader inputs (position, UV, normals, etc.)
 //! - **Output**: Fragment shader outputs
+//! - **Flow**: Branches, loops, conditions and discard
+//! - **Variable**: Mutable local variables
+//! - **Parameter**: Material parameters, textures and samplers
 //!
 //! ## Usage
 //!
@@ -41,6 +44,43 @@
 //!         .with_source("input * 2.0")
 //! }
 //! ```
+//!
+//! Shader-specific details such as param defaults are registered separately:
+//!
+//! ```rust
+//! use wgsl_std::{SHADER_NODE_INFO, ShaderNodeInfo};
+//! use linkme::distributed_slice;
+//!
+//! #[distributed_slice(SHADER_NODE_INFO)]
+//! pub fn my_custom_node_info() -> ShaderNodeInfo {
+//!     ShaderNodeInfo::new("my_node")
+//!         .with_param_default("input", "1.0")
+//! }
+//! ```
+//!
+//! Nodes that work on several types declare a generic type parameter and use
+//! its name as a param or return type. The compiler resolves it from the
+//! connected inputs:
+//!
+//! ```rust
+//! use wgsl_std::{SHADER_REGISTRY, SHADER_NODE_INFO, ShaderNodeInfo, FLOAT_TYPES};
+//! use graphy::core::{NodeMetadata, NodeTypes, ParamInfo};
+//! use linkme::distributed_slice;
+//!
+//! #[distributed_slice(SHADER_REGISTRY)]
+//! pub fn my_generic_node() -> NodeMetadata {
+//!     NodeMetadata::new("my_generic_node", NodeTypes::pure, "Custom")
+//!         .with_params(vec![ParamInfo::new("input", "T")])
+//!         .with_return_type("T")
+//!         .with_source("input * 2.0")
+//! }
+//!
+//! #[distributed_slice(SHADER_NODE_INFO)]
+//! pub fn my_generic_node_info() -> ShaderNodeInfo {
+//!     ShaderNodeInfo::new("my_generic_node")
+//!         .with_generic("T", FLOAT_TYPES)
+//! }
+//! ```
 
 pub mod shader;
 
@@ -53,9 +93,17 @@ use linkme::distributed_slice;
 #[distributed_slice]
 pub static SHADER_REGISTRY: [fn() -> NodeMetadata] = [..];
 
+/// Global registry of shader-specific node info (param defaults, generics, outputs, ...)
+///
+/// Entries are matched to `SHADER_REGISTRY` nodes by name. Nodes without an
+/// entry use the defaults of [`ShaderNodeInfo`].
+#[distributed_slice]
+pub static SHADER_NODE_INFO: [fn() -> ShaderNodeInfo] = [..];
+
 /// Re-export graphy types for convenience
 pub use graphy::core::{NodeTypes, ParamInfo, TypeInfo};
 pub use graphy::DataType;
 
 // Re-export shader node categories
 pub use shader::nodes::*;
+pub use shader::info::{NodeOutput, ShaderNodeInfo, FLOAT_TYPES, FLOAT_VECTOR_TYPES};
@@ -0,0 +1,162 @@
+//! Shader node info
+//!
+//! Shader-specific node details that graphy's `NodeMetadata` has no room for
+
+use serde::{Deserialize, Serialize};
+
+/// Float scalar and vector types, for generic math nodes
+pub const FLOAT_TYPES: &[&str] = &["f32", "vec2<f32>", "vec3<f32>", "vec4<f32>"];
+
+/// Float vector types, for generic vector nodes
+pub const FLOAT_VECTOR_TYPES: &[&str] = &["vec2<f32>", "vec3<f32>", "vec4<f32>"];
+
+/// A named output pin that reads components of the node's value
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeOutput {
+    /// Output pin name
+    pub name: String,
+    /// WGSL type of the output
+    pub output_type: String,
+    /// Swizzle applied to the node's value, e.g. `x` or `rgb`
+    pub swizzle: String,
+}
+
+/// Extra information about a shader node, keyed by node name
+///
+/// Registered alongside the node's `NodeMetadata` through
+/// [`SHADER_NODE_INFO`](crate::SHADER_NODE_INFO).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShaderNodeInfo {
+    /// Name of the node this info belongs to
+    pub name: String,
+    /// WGSL expressions used for params that aren't connected
+    pub param_defaults: Vec<(String, String)>,
+    /// Generic type parameters and the concrete types each may resolve to
+    ///
+    /// Params and return types that use a generic's name as their type take
+    /// the concrete type resolved from the node's connected inputs.
+    pub generics: Vec<(String, Vec<String>)>,
+    /// Named outputs in addition to the node's return value
+    pub outputs: Vec<NodeOutput>,
+    /// Property holding a swizzle mask applied to the node's return value
+    ///
+    /// The output type follows from the mask, e.g. `xy` on a `vec4<f32>`
+    /// gives a `vec2<f32>`.
+    pub swizzle_property: Option<String>,
+    /// Shader stages (`vertex`, `fragment`, `compute`) the node may be used
+    /// in, empty for all stages
+    pub stages: Vec<String>,
+    /// Generic type parameter bound to the type of the local variable the
+    /// node accesses, for variable nodes
+    ///
+    /// The variable is named by the node's `variable` property, and the
+    /// `variable` identifier in the node's source refers to it.
+    pub variable_generic: Option<String>,
+    /// Generic type parameter bound to the type of the material parameter the
+    /// node reads, for parameter nodes
+    ///
+    /// The parameter is named by the node's `parameter` property, and the
+    /// `parameter` identifier in the node's source refers to its uniform field.
+    pub parameter_generic: Option<String>,
+    /// `vec4<f32>` params holding an RGBA color
+    ///
+    /// Only color params accept a `vec3<f32>` with an implicit opaque alpha.
+    pub color_params: Vec<String>,
+}
+
+impl ShaderNodeInfo {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the WGSL expression used when `param` isn't connected
+    pub fn with_param_default(mut self, param: &str, value: &str) -> Self {
+        self.param_defaults.push((param.to_string(), value.to_string()));
+        self
+    }
+
+    /// Default WGSL expression for a param, if one was declared
+    pub fn param_default(&self, param: &str) -> Option<&str> {
+        self.param_defaults
+            .iter()
+            .find(|(name, _)| name == param)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Declare a generic type parameter that resolves to one of `types`
+    ///
+    /// The first type is used when no input using the generic is connected.
+    pub fn with_generic(mut self, name: &str, types: &[&str]) -> Self {
+        self.generics.push((
+            name.to_string(),
+            types.iter().map(|t| t.to_string()).collect(),
+        ));
+        self
+    }
+
+    /// Concrete types a generic type parameter may resolve to
+    pub fn generic(&self, name: &str) -> Option<&[String]> {
+        self.generics
+            .iter()
+            .find(|(generic, _)| generic == name)
+            .map(|(_, types)| types.as_slice())
+    }
+
+    /// Add a named output that reads `swizzle` components of the node's value
+    pub fn with_output(mut self, name: &str, output_type: &str, swizzle: &str) -> Self {
+        self.outputs.push(NodeOutput {
+            name: name.to_string(),
+            output_type: output_type.to_string(),
+            swizzle: swizzle.to_string(),
+        });
+        self
+    }
+
+    /// Swizzle the node's return value with the mask stored in `property`
+    pub fn with_swizzle_property(mut self, property: &str) -> Self {
+        self.swizzle_property = Some(property.to_string());
+        self
+    }
+
+    /// Restrict the node to the given shader stages
+    pub fn with_stages(mut self, stages: &[&str]) -> Self {
+        self.stages = stages.iter().map(|stage| stage.to_string()).collect();
+        self
+    }
+
+    /// Mark the node as accessing a local variable whose type binds `generic`
+    pub fn with_variable_generic(mut self, generic: &str) -> Self {
+        self.variable_generic = Some(generic.to_string());
+        self
+    }
+
+    /// Mark the node as reading a material parameter whose type binds `generic`
+    pub fn with_parameter_generic(mut self, generic: &str) -> Self {
+        self.parameter_generic = Some(generic.to_string());
+        self
+    }
+
+    /// Mark `params` as holding RGBA colors
+    pub fn with_color_params(mut self, params: &[&str]) -> Self {
+        self.color_params.extend(params.iter().map(|param| param.to_string()));
+        self
+    }
+
+    /// Whether a param holds an RGBA color
+    pub fn is_color_param(&self, param: &str) -> bool {
+        self.color_params.iter().any(|name| name == param)
+    }
+
+    /// Whether the node may be used in the given shader stage
+    pub fn supports_stage(&self, stage: &str) -> bool {
+        self.stages.is_empty() || self.stages.iter().any(|s| s == stage)
+    }
+
+    /// Named output with the given pin name, if one was declared
+    pub fn output(&self, name: &str) -> Option<&NodeOutput> {
+        self.outputs.iter().find(|output| output.name == name)
+    }
+}
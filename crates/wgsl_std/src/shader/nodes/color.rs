@@ -2,7 +2,7 @@
 //!
 //! Color operations and transformations
 
-use crate::SHADER_REGISTRY;
+use crate::{SHADER_REGISTRY, SHADER_NODE_INFO, ShaderNodeInfo};
 use graphy::core::{NodeMetadata, NodeTypes, ParamInfo};
 use linkme::distributed_slice;
 
@@ -35,6 +35,35 @@ pub fn rgba() -> NodeMetadata {
         .with_source("vec4(r, g, b, a)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn rgba_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("rgba")
+        .with_param_default("a", "1.0")
+}
+
+// ============================================================================
+// Color Components
+// ============================================================================
+
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn color_split() -> NodeMetadata {
+    NodeMetadata::new("color_split", NodeTypes::pure, "Color")
+        .with_params(vec![ParamInfo::new("color", "vec4<f32>")])
+        .with_return_type("vec4<f32>")
+        .with_source("color")
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn color_split_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("color_split")
+        .with_color_params(&["color"])
+        .with_output("r", "f32", "r")
+        .with_output("g", "f32", "g")
+        .with_output("b", "f32", "b")
+        .with_output("a", "f32", "a")
+        .with_output("rgb", "vec3<f32>", "rgb")
+}
+
 // ============================================================================
 // Color Operations
 // ============================================================================
@@ -51,6 +80,13 @@ pub fn color_lerp() -> NodeMetadata {
         .with_source("mix(a, b, t)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn color_lerp_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("color_lerp")
+        .with_param_default("t", "0.5")
+        .with_color_params(&["a", "b"])
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn color_multiply() -> NodeMetadata {
     NodeMetadata::new("color_multiply", NodeTypes::pure, "Color")
@@ -62,6 +98,13 @@ pub fn color_multiply() -> NodeMetadata {
         .with_source("color * factor")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn color_multiply_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("color_multiply")
+        .with_param_default("factor", "1.0")
+        .with_color_params(&["color"])
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn color_add() -> NodeMetadata {
     NodeMetadata::new("color_add", NodeTypes::pure, "Color")
@@ -73,6 +116,12 @@ pub fn color_add() -> NodeMetadata {
         .with_source("a + b")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn color_add_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("color_add")
+        .with_color_params(&["a", "b"])
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn desaturate() -> NodeMetadata {
     NodeMetadata::new("desaturate", NodeTypes::pure, "Color")
@@ -86,3 +135,9 @@ pub fn desaturate() -> NodeMetadata {
         )
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn desaturate_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("desaturate")
+        .with_param_default("amount", "1.0")
+}
+
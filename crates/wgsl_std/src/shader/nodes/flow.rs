@@ -0,0 +1,96 @@
+//! Flow control shader nodes
+//!
+//! Branches, loops, conditions and fragment discard
+
+use crate::{SHADER_REGISTRY, SHADER_NODE_INFO, ShaderNodeInfo, FLOAT_TYPES};
+use graphy::core::{NodeMetadata, NodeTypes, ParamInfo};
+use linkme::distributed_slice;
+
+// ============================================================================
+// Execution Flow
+// ============================================================================
+
+/// `if condition { Then } else { Else }`
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn branch() -> NodeMetadata {
+    NodeMetadata::new("branch", NodeTypes::control_flow, "Flow")
+        .with_params(vec![ParamInfo::new("condition", "bool")])
+        .with_exec_outputs(vec!["Then".to_string(), "Else".to_string()])
+}
+
+/// Runs `Body` `count` times, then continues with `Completed`
+///
+/// The return value is the iteration index, only readable from `Body`.
+/// Counts are bounded: constants above the compiler's iteration limit are
+/// rejected and connected counts are clamped to it.
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn for_loop() -> NodeMetadata {
+    NodeMetadata::new("for_loop", NodeTypes::control_flow, "Flow")
+        .with_params(vec![ParamInfo::new("count", "u32")])
+        .with_return_type("u32")
+        .with_exec_outputs(vec!["Body".to_string(), "Completed".to_string()])
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn for_loop_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("for_loop")
+        .with_param_default("count", "1")
+}
+
+/// Discards the current fragment
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn discard() -> NodeMetadata {
+    NodeMetadata::new("discard", NodeTypes::fn_, "Flow")
+        .with_source("discard")
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn discard_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("discard")
+        .with_stages(&["fragment"])
+}
+
+// ============================================================================
+// Conditions
+// ============================================================================
+
+/// `true_value` if `condition` holds, otherwise `false_value`
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn select() -> NodeMetadata {
+    NodeMetadata::new("select", NodeTypes::pure, "Flow")
+        .with_params(vec![
+            ParamInfo::new("false_value", "T"),
+            ParamInfo::new("true_value", "T"),
+            ParamInfo::new("condition", "bool"),
+        ])
+        .with_return_type("T")
+        .with_source("select(false_value, true_value, condition)")
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn select_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("select")
+        .with_generic("T", FLOAT_TYPES)
+}
+
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn less_than() -> NodeMetadata {
+    NodeMetadata::new("less_than", NodeTypes::pure, "Flow")
+        .with_params(vec![
+            ParamInfo::new("a", "f32"),
+            ParamInfo::new("b", "f32"),
+        ])
+        .with_return_type("bool")
+        .with_source("a < b")
+}
+
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn greater_than() -> NodeMetadata {
+    NodeMetadata::new("greater_than", NodeTypes::pure, "Flow")
+        .with_params(vec![
+            ParamInfo::new("a", "f32"),
+            ParamInfo::new("b", "f32"),
+        ])
+        .with_return_type("bool")
+        .with_source("a > b")
+}
@@ -1,6 +1,6 @@
 //! Shader input nodes
 //!
-//! Vertex shader inputs and fragment shader inputs
+//! Vertex, fragment and compute shader inputs
 
 use crate::SHADER_REGISTRY;
 use graphy::core::{NodeMetadata, NodeTypes, ParamInfo};
@@ -63,3 +63,55 @@ pub fn frag_normal() -> NodeMetadata {
         .with_source("frag_normal")
         }
 
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn frag_color() -> NodeMetadata {
+    NodeMetadata::new("frag_color", NodeTypes::pure, "Input")
+        .with_return_type("vec4<f32>")
+        .with_source("frag_color")
+}
+
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn frag_world_position() -> NodeMetadata {
+    NodeMetadata::new("frag_world_position", NodeTypes::pure, "Input")
+        .with_return_type("vec3<f32>")
+        .with_source("frag_world_position")
+}
+
+// ============================================================================
+// Compute Inputs
+// ============================================================================
+
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn global_invocation_id() -> NodeMetadata {
+    NodeMetadata::new("global_invocation_id", NodeTypes::pure, "Input")
+        .with_return_type("vec3<u32>")
+        .with_source("global_invocation_id")
+}
+
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn local_invocation_id() -> NodeMetadata {
+    NodeMetadata::new("local_invocation_id", NodeTypes::pure, "Input")
+        .with_return_type("vec3<u32>")
+        .with_source("local_invocation_id")
+}
+
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn local_invocation_index() -> NodeMetadata {
+    NodeMetadata::new("local_invocation_index", NodeTypes::pure, "Input")
+        .with_return_type("u32")
+        .with_source("local_invocation_index")
+}
+
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn workgroup_id() -> NodeMetadata {
+    NodeMetadata::new("workgroup_id", NodeTypes::pure, "Input")
+        .with_return_type("vec3<u32>")
+        .with_source("workgroup_id")
+}
+
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn num_workgroups() -> NodeMetadata {
+    NodeMetadata::new("num_workgroups", NodeTypes::pure, "Input")
+        .with_return_type("vec3<u32>")
+        .with_source("num_workgroups")
+}
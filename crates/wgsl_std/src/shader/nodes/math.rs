@@ -1,8 +1,11 @@
 //! Math shader nodes
 //!
 //! Basic mathematical operations for shaders
+//!
+//! Every node is generic over `T`, which resolves to `f32` or a float vector
+//! from the connected inputs. Scalar inputs are splatted when mixed with vectors.
 
-use crate::SHADER_REGISTRY;
+use crate::{SHADER_REGISTRY, SHADER_NODE_INFO, ShaderNodeInfo, FLOAT_TYPES};
 use graphy::core::{NodeMetadata, NodeTypes, ParamInfo};
 use linkme::distributed_slice;
 
@@ -14,46 +17,72 @@ use linkme::distributed_slice;
 pub fn add() -> NodeMetadata {
     NodeMetadata::new("add", NodeTypes::pure, "Math")
         .with_params(vec![
-            ParamInfo::new("a", "f32"),
-            ParamInfo::new("b", "f32"),
+            ParamInfo::new("a", "T"),
+            ParamInfo::new("b", "T"),
         ])
-        .with_return_type("f32")
+        .with_return_type("T")
         .with_source("a + b")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn add_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("add")
+        .with_generic("T", FLOAT_TYPES)
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn subtract() -> NodeMetadata {
     NodeMetadata::new("subtract", NodeTypes::pure, "Math")
         .with_params(vec![
-            ParamInfo::new("a", "f32"),
-            ParamInfo::new("b", "f32"),
+            ParamInfo::new("a", "T"),
+            ParamInfo::new("b", "T"),
         ])
-        .with_return_type("f32")
+        .with_return_type("T")
         .with_source("a - b")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn subtract_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("subtract")
+        .with_generic("T", FLOAT_TYPES)
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn multiply() -> NodeMetadata {
     NodeMetadata::new("multiply", NodeTypes::pure, "Math")
         .with_params(vec![
-            ParamInfo::new("a", "f32"),
-            ParamInfo::new("b", "f32"),
+            ParamInfo::new("a", "T"),
+            ParamInfo::new("b", "T"),
         ])
-        .with_return_type("f32")
+        .with_return_type("T")
         .with_source("a * b")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn multiply_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("multiply")
+        .with_generic("T", FLOAT_TYPES)
+        .with_param_default("b", "1.0")
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn divide() -> NodeMetadata {
     NodeMetadata::new("divide", NodeTypes::pure, "Math")
         .with_params(vec![
-            ParamInfo::new("a", "f32"),
-            ParamInfo::new("b", "f32"),
+            ParamInfo::new("a", "T"),
+            ParamInfo::new("b", "T"),
         ])
-        .with_return_type("f32")
+        .with_return_type("T")
         .with_source("a / b")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn divide_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("divide")
+        .with_generic("T", FLOAT_TYPES)
+        .with_param_default("b", "1.0")
+}
+
 // ============================================================================
 // Trigonometry
 // ============================================================================
@@ -61,27 +90,45 @@ pub fn divide() -> NodeMetadata {
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn sin() -> NodeMetadata {
     NodeMetadata::new("sin", NodeTypes::pure, "Math")
-        .with_params(vec![ParamInfo::new("x", "f32")])
-        .with_return_type("f32")
+        .with_params(vec![ParamInfo::new("x", "T")])
+        .with_return_type("T")
         .with_source("sin(x)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn sin_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("sin")
+        .with_generic("T", FLOAT_TYPES)
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn cos() -> NodeMetadata {
     NodeMetadata::new("cos", NodeTypes::pure, "Math")
-        .with_params(vec![ParamInfo::new("x", "f32")])
-        .with_return_type("f32")
+        .with_params(vec![ParamInfo::new("x", "T")])
+        .with_return_type("T")
         .with_source("cos(x)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn cos_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("cos")
+        .with_generic("T", FLOAT_TYPES)
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn tan() -> NodeMetadata {
     NodeMetadata::new("tan", NodeTypes::pure, "Math")
-        .with_params(vec![ParamInfo::new("x", "f32")])
-        .with_return_type("f32")
+        .with_params(vec![ParamInfo::new("x", "T")])
+        .with_return_type("T")
         .with_source("tan(x)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn tan_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("tan")
+        .with_generic("T", FLOAT_TYPES)
+}
+
 // ============================================================================
 // Interpolation
 // ============================================================================
@@ -90,38 +137,60 @@ pub fn tan() -> NodeMetadata {
 pub fn lerp() -> NodeMetadata {
     NodeMetadata::new("lerp", NodeTypes::pure, "Math")
         .with_params(vec![
-            ParamInfo::new("a", "f32"),
-            ParamInfo::new("b", "f32"),
-            ParamInfo::new("t", "f32"),
+            ParamInfo::new("a", "T"),
+            ParamInfo::new("b", "T"),
+            ParamInfo::new("t", "T"),
         ])
-        .with_return_type("f32")
+        .with_return_type("T")
         .with_source("mix(a, b, t)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn lerp_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("lerp")
+        .with_generic("T", FLOAT_TYPES)
+        .with_param_default("t", "0.5")
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn clamp() -> NodeMetadata {
     NodeMetadata::new("clamp", NodeTypes::pure, "Math")
         .with_params(vec![
-            ParamInfo::new("value", "f32"),
-            ParamInfo::new("min", "f32"),
-            ParamInfo::new("max", "f32"),
+            ParamInfo::new("value", "T"),
+            ParamInfo::new("min", "T"),
+            ParamInfo::new("max", "T"),
         ])
-        .with_return_type("f32")
+        .with_return_type("T")
         .with_source("clamp(value, min, max)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn clamp_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("clamp")
+        .with_generic("T", FLOAT_TYPES)
+        .with_param_default("min", "0.0")
+        .with_param_default("max", "1.0")
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn smoothstep() -> NodeMetadata {
     NodeMetadata::new("smoothstep", NodeTypes::pure, "Math")
         .with_params(vec![
-            ParamInfo::new("edge0", "f32"),
-            ParamInfo::new("edge1", "f32"),
-            ParamInfo::new("x", "f32"),
+            ParamInfo::new("edge0", "T"),
+            ParamInfo::new("edge1", "T"),
+            ParamInfo::new("x", "T"),
         ])
-        .with_return_type("f32")
+        .with_return_type("T")
         .with_source("smoothstep(edge0, edge1, x)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn smoothstep_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("smoothstep")
+        .with_generic("T", FLOAT_TYPES)
+        .with_param_default("edge1", "1.0")
+}
+
 // ============================================================================
 // Other Math
 // ============================================================================
@@ -130,34 +199,59 @@ pub fn smoothstep() -> NodeMetadata {
 pub fn pow() -> NodeMetadata {
     NodeMetadata::new("pow", NodeTypes::pure, "Math")
         .with_params(vec![
-            ParamInfo::new("base", "f32"),
-            ParamInfo::new("exponent", "f32"),
+            ParamInfo::new("base", "T"),
+            ParamInfo::new("exponent", "T"),
         ])
-        .with_return_type("f32")
+        .with_return_type("T")
         .with_source("pow(base, exponent)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn pow_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("pow")
+        .with_generic("T", FLOAT_TYPES)
+        .with_param_default("exponent", "1.0")
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn sqrt() -> NodeMetadata {
     NodeMetadata::new("sqrt", NodeTypes::pure, "Math")
-        .with_params(vec![ParamInfo::new("x", "f32")])
-        .with_return_type("f32")
+        .with_params(vec![ParamInfo::new("x", "T")])
+        .with_return_type("T")
         .with_source("sqrt(x)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn sqrt_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("sqrt")
+        .with_generic("T", FLOAT_TYPES)
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn abs() -> NodeMetadata {
     NodeMetadata::new("abs", NodeTypes::pure, "Math")
-        .with_params(vec![ParamInfo::new("x", "f32")])
-        .with_return_type("f32")
+        .with_params(vec![ParamInfo::new("x", "T")])
+        .with_return_type("T")
         .with_source("abs(x)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn abs_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("abs")
+        .with_generic("T", FLOAT_TYPES)
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn fract() -> NodeMetadata {
     NodeMetadata::new("fract", NodeTypes::pure, "Math")
-        .with_params(vec![ParamInfo::new("x", "f32")])
-        .with_return_type("f32")
+        .with_params(vec![ParamInfo::new("x", "T")])
+        .with_return_type("T")
         .with_source("fract(x)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn fract_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("fract")
+        .with_generic("T", FLOAT_TYPES)
+}
+
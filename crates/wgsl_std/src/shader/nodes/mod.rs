@@ -4,6 +4,9 @@ pub mod color;
 pub mod texture;
 pub mod input;
 pub mod output;
+pub mod flow;
+pub mod variable;
+pub mod parameter;
 
 // Re-export for easy access
 pub use math::*;
@@ -12,4 +15,7 @@ pub use color::*;
 pub use texture::*;
 pub use input::*;
 pub use output::*;
+pub use flow::*;
+pub use variable::*;
+pub use parameter::*;
 
@@ -1,8 +1,8 @@
 //! Shader output nodes
 //!
-//! Output nodes for vertex and fragment shaders
+//! Entry points and output nodes for vertex, fragment and compute shaders
 
-use crate::SHADER_REGISTRY;
+use crate::{SHADER_REGISTRY, SHADER_NODE_INFO, ShaderNodeInfo};
 use graphy::core::{NodeMetadata, NodeTypes, ParamInfo};
 use linkme::distributed_slice;
 
@@ -22,8 +22,18 @@ pub fn vertex_main() -> NodeMetadata {
         .with_exec_outputs(vec!["Body".to_string()])
 }
 
+/// Compute entry point
+///
+/// The workgroup size is read from the `workgroup_size_x`, `workgroup_size_y`
+/// and `workgroup_size_z` node properties (defaults to 8x8x1).
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn compute_main() -> NodeMetadata {
+    NodeMetadata::new("compute_main", NodeTypes::event, "Entry")
+        .with_exec_outputs(vec!["Body".to_string()])
+}
+
 // ============================================================================
-// Fragment Outputs
+// Stage Outputs
 // ============================================================================
 
 #[distributed_slice(SHADER_REGISTRY)]
@@ -34,10 +44,29 @@ pub fn fragment_output() -> NodeMetadata {
         ])
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn fragment_output_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("fragment_output")
+        .with_param_default("color", "vec4<f32>(0.0, 0.0, 0.0, 1.0)")
+        .with_color_params(&["color"])
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn vertex_output() -> NodeMetadata {
     NodeMetadata::new("vertex_output", NodeTypes::event, "Output")
         .with_params(vec![
             ParamInfo::new("position", "vec4<f32>"),
+            // Optional varyings, only emitted when connected
+            ParamInfo::new("uv", "vec2<f32>"),
+            ParamInfo::new("normal", "vec3<f32>"),
+            ParamInfo::new("color", "vec4<f32>"),
+            ParamInfo::new("world_position", "vec3<f32>"),
         ])
 }
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn vertex_output_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("vertex_output")
+        .with_param_default("position", "vec4<f32>(0.0, 0.0, 0.0, 1.0)")
+        .with_color_params(&["color"])
+}
@@ -0,0 +1,73 @@
+//! Material parameter shader nodes
+//!
+//! Values, textures and samplers exposed to the material
+
+use crate::{SHADER_REGISTRY, SHADER_NODE_INFO, ShaderNodeInfo};
+use graphy::core::{NodeMetadata, NodeTypes};
+use linkme::distributed_slice;
+
+// ============================================================================
+// Uniform Parameters
+// ============================================================================
+//
+// Parameters are declared at graph level with their type (`float`, `int`,
+// `bool`, `color` or a WGSL scalar/vector type) and default value. Nodes name
+// the parameter they read in the `parameter` property.
+
+/// Reads a material parameter from the uniform buffer
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn parameter() -> NodeMetadata {
+    NodeMetadata::new("parameter", NodeTypes::pure, "Parameter")
+        .with_return_type("T")
+        .with_source("parameter")
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn parameter_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("parameter")
+        .with_parameter_generic("T")
+}
+
+// ============================================================================
+// Resource Parameters
+// ============================================================================
+//
+// Textures and samplers are bound as their own `@group`/`@binding` globals.
+// Nodes reading the same `parameter` share one binding.
+
+/// Reads a texture bound to the material
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn texture_parameter() -> NodeMetadata {
+    NodeMetadata::new("texture_parameter", NodeTypes::pure, "Parameter")
+        .with_return_type("T")
+        .with_source("parameter")
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn texture_parameter_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("texture_parameter")
+        .with_parameter_generic("T")
+        .with_generic("T", &[
+            "texture_2d<f32>",
+            "texture_2d_array<f32>",
+            "texture_cube<f32>",
+            "texture_3d<f32>",
+            "texture_depth_2d",
+        ])
+}
+
+/// Reads a sampler bound to the material: a `filtering` or `non_filtering`
+/// sampler, or a `comparison` sampler for depth comparisons
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn sampler_parameter() -> NodeMetadata {
+    NodeMetadata::new("sampler_parameter", NodeTypes::pure, "Parameter")
+        .with_return_type("T")
+        .with_source("parameter")
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn sampler_parameter_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("sampler_parameter")
+        .with_parameter_generic("T")
+        .with_generic("T", &["sampler", "sampler_comparison"])
+}
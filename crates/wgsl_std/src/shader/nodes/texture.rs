@@ -49,3 +49,16 @@ pub fn sample_texture_grad() -> NodeMetadata {
         .with_source("textureSampleGrad(texture, sampler, uv, ddx, ddy)")
 }
 
+/// Compares `depth_ref` against a depth texture, for shadow lookups
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn sample_texture_compare() -> NodeMetadata {
+    NodeMetadata::new("sample_texture_compare", NodeTypes::pure, "Texture")
+        .with_params(vec![
+            ParamInfo::new("texture", "texture_depth_2d"),
+            ParamInfo::new("sampler", "sampler_comparison"),
+            ParamInfo::new("uv", "vec2<f32>"),
+            ParamInfo::new("depth_ref", "f32"),
+        ])
+        .with_return_type("f32")
+        .with_source("textureSampleCompare(texture, sampler, uv, depth_ref)")
+}
@@ -0,0 +1,45 @@
+//! Local variable shader nodes
+//!
+//! Read and write mutable local variables of the entry function
+
+use crate::{SHADER_REGISTRY, SHADER_NODE_INFO, ShaderNodeInfo};
+use graphy::core::{NodeMetadata, NodeTypes, ParamInfo};
+use linkme::distributed_slice;
+
+// ============================================================================
+// Local Variables
+// ============================================================================
+//
+// Each variable is declared once in the graph-level declarations the graph is
+// compiled with, which give its name, WGSL type and optional initial value.
+// `set_variable` and `get_variable` nodes refer to it by name through their
+// `variable` property.
+
+/// Assigns `value` to the variable, in execution order
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn set_variable() -> NodeMetadata {
+    NodeMetadata::new("set_variable", NodeTypes::fn_, "Variable")
+        .with_params(vec![ParamInfo::new("value", "T")])
+        .with_exec_outputs(vec!["Then".to_string()])
+        .with_source("variable = value")
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn set_variable_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("set_variable")
+        .with_variable_generic("T")
+}
+
+/// Reads the current value of the variable
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn get_variable() -> NodeMetadata {
+    NodeMetadata::new("get_variable", NodeTypes::pure, "Variable")
+        .with_return_type("T")
+        .with_source("variable")
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn get_variable_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("get_variable")
+        .with_variable_generic("T")
+}
@@ -1,8 +1,11 @@
 //! Vector shader nodes
 //!
 //! Vector operations for 2D, 3D, and 4D vectors
+//!
+//! Operations that aren't tied to a vector size are generic over `T`, which
+//! resolves to `vec2<f32>`, `vec3<f32>` or `vec4<f32>` from the connected inputs.
 
-use crate::SHADER_REGISTRY;
+use crate::{SHADER_REGISTRY, SHADER_NODE_INFO, ShaderNodeInfo, FLOAT_VECTOR_TYPES};
 use graphy::core::{NodeMetadata, NodeTypes, ParamInfo};
 use linkme::distributed_slice;
 
@@ -46,6 +49,12 @@ pub fn vec4_construct() -> NodeMetadata {
         .with_source("vec4(x, y, z, w)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn vec4_construct_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("vec4")
+        .with_param_default("w", "1.0")
+}
+
 // ============================================================================
 // Vector Operations
 // ============================================================================
@@ -53,22 +62,34 @@ pub fn vec4_construct() -> NodeMetadata {
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn vec3_normalize() -> NodeMetadata {
     NodeMetadata::new("normalize", NodeTypes::pure, "Vector")
-        .with_params(vec![ParamInfo::new("v", "vec3<f32>")])
-        .with_return_type("vec3<f32>")
+        .with_params(vec![ParamInfo::new("v", "T")])
+        .with_return_type("T")
         .with_source("normalize(v)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn vec3_normalize_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("normalize")
+        .with_generic("T", FLOAT_VECTOR_TYPES)
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn vec3_dot() -> NodeMetadata {
     NodeMetadata::new("dot", NodeTypes::pure, "Vector")
         .with_params(vec![
-            ParamInfo::new("a", "vec3<f32>"),
-            ParamInfo::new("b", "vec3<f32>"),
+            ParamInfo::new("a", "T"),
+            ParamInfo::new("b", "T"),
         ])
         .with_return_type("f32")
         .with_source("dot(a, b)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn vec3_dot_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("dot")
+        .with_generic("T", FLOAT_VECTOR_TYPES)
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn vec3_cross() -> NodeMetadata {
     NodeMetadata::new("cross", NodeTypes::pure, "Vector")
@@ -83,26 +104,53 @@ pub fn vec3_cross() -> NodeMetadata {
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn vec3_length() -> NodeMetadata {
     NodeMetadata::new("length", NodeTypes::pure, "Vector")
-        .with_params(vec![ParamInfo::new("v", "vec3<f32>")])
+        .with_params(vec![ParamInfo::new("v", "T")])
         .with_return_type("f32")
         .with_source("length(v)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn vec3_length_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("length")
+        .with_generic("T", FLOAT_VECTOR_TYPES)
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn vec3_distance() -> NodeMetadata {
     NodeMetadata::new("distance", NodeTypes::pure, "Vector")
         .with_params(vec![
-            ParamInfo::new("a", "vec3<f32>"),
-            ParamInfo::new("b", "vec3<f32>"),
+            ParamInfo::new("a", "T"),
+            ParamInfo::new("b", "T"),
         ])
         .with_return_type("f32")
         .with_source("distance(a, b)")
 }
 
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn vec3_distance_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("distance")
+        .with_generic("T", FLOAT_VECTOR_TYPES)
+}
+
 // ============================================================================
 // Component Access
 // ============================================================================
 
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn vec2_split() -> NodeMetadata {
+    NodeMetadata::new("vec2_split", NodeTypes::pure, "Vector")
+        .with_params(vec![ParamInfo::new("v", "vec2<f32>")])
+        .with_return_type("vec2<f32>")
+        .with_source("v")
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn vec2_split_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("vec2_split")
+        .with_output("x", "f32", "x")
+        .with_output("y", "f32", "y")
+}
+
 #[distributed_slice(SHADER_REGISTRY)]
 pub fn vec3_split() -> NodeMetadata {
     NodeMetadata::new("vec3_split", NodeTypes::pure, "Vector")
@@ -110,3 +158,46 @@ pub fn vec3_split() -> NodeMetadata {
         .with_return_type("vec3<f32>")
         .with_source("v")
 }
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn vec3_split_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("vec3_split")
+        .with_output("x", "f32", "x")
+        .with_output("y", "f32", "y")
+        .with_output("z", "f32", "z")
+        .with_output("xy", "vec2<f32>", "xy")
+}
+
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn vec4_split() -> NodeMetadata {
+    NodeMetadata::new("vec4_split", NodeTypes::pure, "Vector")
+        .with_params(vec![ParamInfo::new("v", "vec4<f32>")])
+        .with_return_type("vec4<f32>")
+        .with_source("v")
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn vec4_split_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("vec4_split")
+        .with_output("x", "f32", "x")
+        .with_output("y", "f32", "y")
+        .with_output("z", "f32", "z")
+        .with_output("w", "f32", "w")
+        .with_output("xyz", "vec3<f32>", "xyz")
+}
+
+/// Swizzle a vector with the mask in the `mask` property (e.g. `xy`, `zyx`, `rgb`)
+#[distributed_slice(SHADER_REGISTRY)]
+pub fn swizzle() -> NodeMetadata {
+    NodeMetadata::new("swizzle", NodeTypes::pure, "Vector")
+        .with_params(vec![ParamInfo::new("v", "T")])
+        .with_return_type("T")
+        .with_source("v")
+}
+
+#[distributed_slice(SHADER_NODE_INFO)]
+pub fn swizzle_info() -> ShaderNodeInfo {
+    ShaderNodeInfo::new("swizzle")
+        .with_generic("T", FLOAT_VECTOR_TYPES)
+        .with_swizzle_property("mask")
+}